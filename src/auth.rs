@@ -0,0 +1,110 @@
+//! Codex credential checks: the `openbot auth status` command and the
+//! pre-flight login check before a run.
+
+use anyhow::{Context, Result};
+use codex_core::AuthManager;
+use codex_core::config::ConfigBuilder;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// How to fix missing or rejected credentials.
+pub const LOGIN_HINT: &str =
+    "run `codex login` (or set OPENAI_API_KEY), then check with `openbot auth status`";
+
+/// Fail before the first turn when codex has no credentials at all, rather
+/// than letting the turn fail with an opaque error.
+pub async fn require_login(auth_manager: &AuthManager) -> Result<()> {
+    if auth_manager.auth().await.is_none() {
+        anyhow::bail!("not logged in to codex: {LOGIN_HINT}");
+    }
+    Ok(())
+}
+
+/// Whether a codex error message looks like rejected credentials, which
+/// retrying won't fix.
+///
+/// Matches whole words only, so a request ID or byte count that happens to
+/// contain `401` doesn't end the run.
+pub fn is_auth_error(message: &str) -> bool {
+    let words: Vec<String> = message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let normalized = format!(" {} ", words.join(" "));
+    [
+        "status 401",
+        "status code 401",
+        "http 401",
+        "unauthorized",
+        "invalid api key",
+        "incorrect api key",
+        "not logged in",
+        "token expired",
+        "refresh token has expired",
+        "refresh token was already used",
+        "refresh token was revoked",
+        "invalid refresh token",
+    ]
+    .iter()
+    .any(|phrase| normalized.contains(&format!(" {phrase} ")))
+}
+
+/// Load the default codex config and an auth manager for it, returning the
+/// codex home alongside.
+pub async fn load_auth_manager() -> Result<(PathBuf, Arc<AuthManager>)> {
+    let config = ConfigBuilder::default()
+        .build()
+        .await
+        .with_context(|| "building codex config")?;
+    let auth_manager = AuthManager::shared(
+        config.codex_home.clone(),
+        true,
+        config.cli_auth_credentials_store_mode,
+    );
+    Ok((config.codex_home, auth_manager))
+}
+
+/// `openbot auth status`: report whether codex credentials are present and
+/// can produce a token. Errors when they can't.
+pub async fn status() -> Result<()> {
+    let (codex_home, auth_manager) = load_auth_manager().await?;
+
+    println!("Codex home: {}", codex_home.display());
+    let Some(auth) = auth_manager.auth().await else {
+        println!("Credentials: none");
+        anyhow::bail!("not logged in to codex: {LOGIN_HINT}");
+    };
+    println!("Credentials: found");
+    match auth.get_token().await {
+        Ok(_) => {
+            println!("Status: valid");
+            Ok(())
+        }
+        Err(e) => {
+            println!("Status: invalid ({e})");
+            anyhow::bail!("codex credentials were rejected: {LOGIN_HINT}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_errors_are_recognized() {
+        assert!(is_auth_error(
+            "unexpected status 401 Unauthorized: Missing bearer"
+        ));
+        assert!(is_auth_error("Your refresh token has expired"));
+        assert!(!is_auth_error("stream disconnected before completion"));
+        assert!(!is_auth_error("rate limit reached, retry in 20s"));
+        assert!(is_auth_error("request failed: HTTP 401"));
+        // A 401 inside an ID or a count is not a status code.
+        assert!(!is_auth_error(
+            "stream error (request id req_4015ab, read 401 bytes)"
+        ));
+        assert!(!is_auth_error("timed out waiting to refresh token usage"));
+    }
+}
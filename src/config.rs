@@ -1,13 +1,71 @@
 //! Configuration loading, defaults, and path helpers for bots and global data.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-/// The openbot home directory (`~/.openbot`).
+use crate::history::TokenSnapshot;
+use crate::memory::MemoryLimits;
+
+/// The openbot home directory (`~/.openbot` by default).
+///
+/// `OPENBOT_HOME` overrides the location. Otherwise an existing `~/.openbot`
+/// is always used; on Windows a fresh install goes to the platform data dir.
 pub fn openbot_home() -> Result<PathBuf> {
-    let home = std::env::var_os("HOME").ok_or_else(|| anyhow::anyhow!("$HOME not set"))?;
-    Ok(PathBuf::from(home).join(".openbot"))
+    resolve_openbot_home(
+        std::env::var_os("OPENBOT_HOME").map(PathBuf::from),
+        dirs::home_dir(),
+        dirs::data_dir(),
+    )
+    .ok_or_else(|| anyhow::anyhow!("cannot determine home directory; set OPENBOT_HOME"))
+}
+
+/// Pick the openbot home from an explicit override, the user's home dir,
+/// and the platform data dir, in that order of precedence.
+fn resolve_openbot_home(
+    override_dir: Option<PathBuf>,
+    home: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+) -> Option<PathBuf> {
+    if let Some(dir) = override_dir.filter(|d| !d.as_os_str().is_empty()) {
+        return Some(dir);
+    }
+    let legacy = home.map(|h| h.join(".openbot"));
+    if cfg!(windows) && !legacy.as_ref().is_some_and(|p| p.exists()) {
+        if let Some(data) = data_dir {
+            return Some(data.join("openbot"));
+        }
+    }
+    legacy.or_else(|| data_dir.map(|d| d.join("openbot")))
+}
+
+/// Maximum length of a bot name.
+const MAX_BOT_NAME_LEN: usize = 64;
+
+/// Check that a bot name is safe to use as a single directory name.
+///
+/// Names may contain ASCII letters, digits, `-`, `_`, and `.`, must not start
+/// with `.` or `-`, and are limited to 64 characters.
+pub fn validate_bot_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        anyhow::bail!("bot name must not be empty");
+    }
+    if name.len() > MAX_BOT_NAME_LEN {
+        anyhow::bail!("bot name '{name}' is longer than {MAX_BOT_NAME_LEN} characters");
+    }
+    if name.starts_with('.') || name.starts_with('-') {
+        anyhow::bail!("bot name '{name}' must not start with '.' or '-'");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        anyhow::bail!(
+            "bot name '{name}' contains invalid character {c:?} (use letters, digits, '-', '_', '.')"
+        );
+    }
+    Ok(())
 }
 
 /// Return the path to a bot's directory (`~/.openbot/bots/<name>`).
@@ -38,16 +96,58 @@ pub fn bot_workspace_memory_path(name: &str, slug: &str) -> Result<PathBuf> {
         .join("memory.json"))
 }
 
+/// Per-project notes file (`~/.openbot/bots/<name>/workspaces/<slug>/notes.md`).
+pub fn bot_workspace_notes_path(name: &str, slug: &str) -> Result<PathBuf> {
+    Ok(bot_dir(name)?
+        .join("workspaces")
+        .join(slug)
+        .join("notes.md"))
+}
+
 /// Per-project history directory (`~/.openbot/bots/<name>/workspaces/<slug>/history/`).
 pub fn bot_workspace_history_dir(name: &str, slug: &str) -> Result<PathBuf> {
     Ok(bot_dir(name)?.join("workspaces").join(slug).join("history"))
 }
 
+/// Record of the workspaces a bot has run in (`~/.openbot/bots/<name>/workspaces.json`).
+pub fn bot_workspace_registry_path(name: &str) -> Result<PathBuf> {
+    Ok(bot_dir(name)?.join("workspaces.json"))
+}
+
+/// List workspace slugs a bot has data for (`~/.openbot/bots/<name>/workspaces/*`).
+pub fn list_workspaces(name: &str) -> Result<Vec<String>> {
+    let dir = bot_dir(name)?.join("workspaces");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut slugs = Vec::new();
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && let Some(slug) = entry.file_name().to_str()
+        {
+            slugs.push(slug.to_string());
+        }
+    }
+    slugs.sort();
+    Ok(slugs)
+}
+
+/// Global defaults path (`~/.openbot/config.md`), inherited by every bot.
+pub fn global_config_path() -> Result<PathBuf> {
+    Ok(openbot_home()?.join("config.md"))
+}
+
 /// Bot config path (`~/.openbot/bots/<name>/config.md`).
 pub fn bot_config_path(name: &str) -> Result<PathBuf> {
     Ok(bot_dir(name)?.join("config.md"))
 }
 
+/// Optional prompt template path (`~/.openbot/bots/<name>/prompt.md`).
+pub fn bot_prompt_template_path(name: &str) -> Result<PathBuf> {
+    Ok(bot_dir(name)?.join("prompt.md"))
+}
+
 /// Ensure the bot directory structure exists.
 pub fn ensure_bot_dirs(name: &str) -> Result<()> {
     std::fs::create_dir_all(bot_dir(name)?)?;
@@ -82,7 +182,9 @@ pub fn list_bots() -> Result<Vec<String>> {
 
 /// TOML frontmatter fields from config.md.
 /// Instructions come from the markdown body, not from frontmatter.
-#[derive(Debug, Clone, Default, Deserialize)]
+///
+/// `Serialize` exists so [`frontmatter_keys`] can list the field names.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(default)]
 struct Frontmatter {
     description: Option<String>,
@@ -90,13 +192,300 @@ struct Frontmatter {
     sleep_secs: Option<u64>,
     stop_phrase: Option<String>,
     model: Option<String>,
-    sandbox: Option<String>,
+    sandbox: Option<Sandbox>,
     skip_git_check: Option<bool>,
+    init_submodules: Option<bool>,
+    merge_strategy: Option<MergeStrategy>,
+    auto_commit: Option<bool>,
+    max_memory_entries: Option<usize>,
+    max_memory_value_bytes: Option<usize>,
+    max_memory_history: Option<usize>,
+    max_prompt_memory_bytes: Option<usize>,
+    prompt_history_count: Option<usize>,
+    prompt_teach_skills: Option<bool>,
+    include_file_tree: Option<bool>,
+    skill_tags: Option<Vec<String>>,
+    show_reasoning: Option<bool>,
+    notify_url: Option<String>,
+    notify_body: Option<String>,
+    strict_env: Option<bool>,
+    branch_template: Option<String>,
+    default_project: Option<String>,
+    writable_roots: Option<Vec<PathBuf>>,
+    instructions_append: Option<String>,
+    pricing: Option<BTreeMap<String, ModelPrice>>,
+}
+
+impl Frontmatter {
+    /// Fill fields left unset here from `base`.
+    fn layered_over(self, base: Frontmatter) -> Frontmatter {
+        Frontmatter {
+            description: self.description.or(base.description),
+            max_iterations: self.max_iterations.or(base.max_iterations),
+            sleep_secs: self.sleep_secs.or(base.sleep_secs),
+            stop_phrase: self.stop_phrase.or(base.stop_phrase),
+            model: self.model.or(base.model),
+            sandbox: self.sandbox.or(base.sandbox),
+            skip_git_check: self.skip_git_check.or(base.skip_git_check),
+            init_submodules: self.init_submodules.or(base.init_submodules),
+            merge_strategy: self.merge_strategy.or(base.merge_strategy),
+            auto_commit: self.auto_commit.or(base.auto_commit),
+            max_memory_entries: self.max_memory_entries.or(base.max_memory_entries),
+            max_memory_value_bytes: self.max_memory_value_bytes.or(base.max_memory_value_bytes),
+            max_memory_history: self.max_memory_history.or(base.max_memory_history),
+            max_prompt_memory_bytes: self
+                .max_prompt_memory_bytes
+                .or(base.max_prompt_memory_bytes),
+            prompt_history_count: self.prompt_history_count.or(base.prompt_history_count),
+            prompt_teach_skills: self.prompt_teach_skills.or(base.prompt_teach_skills),
+            include_file_tree: self.include_file_tree.or(base.include_file_tree),
+            skill_tags: self.skill_tags.or(base.skill_tags),
+            show_reasoning: self.show_reasoning.or(base.show_reasoning),
+            notify_url: self.notify_url.or(base.notify_url),
+            notify_body: self.notify_body.or(base.notify_body),
+            strict_env: self.strict_env.or(base.strict_env),
+            branch_template: self.branch_template.or(base.branch_template),
+            default_project: self.default_project.or(base.default_project),
+            writable_roots: self.writable_roots.or(base.writable_roots),
+            instructions_append: self.instructions_append.or(base.instructions_append),
+            // Merged per model, so a bot can price one model and inherit the rest.
+            pricing: match (self.pricing, base.pricing) {
+                (Some(mine), Some(mut base)) => {
+                    base.extend(mine);
+                    Some(base)
+                }
+                (mine, base) => mine.or(base),
+            },
+        }
+    }
+}
+
+/// Frontmatter of the global `~/.openbot/config.md`, or empty if there is
+/// none. Its body is ignored; only the frontmatter is inherited.
+fn load_global_defaults() -> Result<Frontmatter> {
+    let path = global_config_path()?;
+    if !path.exists() {
+        return Ok(Frontmatter::default());
+    }
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let (fm, _) = parse_config_md(&contents).with_context(|| format!("in {}", path.display()))?;
+    Ok(fm)
+}
+
+/// Name of the project-local config file looked up from the working directory.
+pub const PROJECT_CONFIG_FILE: &str = ".openbot.toml";
+
+/// Settings a repository can pin for every bot run inside it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectConfig {
+    model: Option<String>,
+    sandbox: Option<Sandbox>,
+    writable_roots: Option<Vec<PathBuf>>,
+    instructions_append: Option<String>,
+}
+
+/// Find the `.openbot.toml` that applies to `start`.
+///
+/// Walks up from `start` and stops at the first directory holding `.git`,
+/// so a file above the repository root is never picked up. Outside a
+/// repository only `start` itself is checked.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    let in_repo = start.ancestors().any(|dir| dir.join(".git").exists());
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_CONFIG_FILE);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if !in_repo || dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// Settings from the `.openbot.toml` that applies to `start`, as
+/// frontmatter, or empty if there is none, plus warnings for the settings
+/// that were dropped.
+///
+/// The file comes with the repository, so it is not trusted to loosen the
+/// sandbox: a `sandbox` less strict than `current` is ignored, and so is
+/// any `writable_roots` entry outside the repository root. Relative roots
+/// resolve against the file's directory.
+fn load_project_defaults(start: &Path, current: Sandbox) -> Result<(Frontmatter, Vec<String>)> {
+    let Some(path) = find_project_config(start) else {
+        return Ok((Frontmatter::default(), Vec::new()));
+    };
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let project: ProjectConfig =
+        toml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let repo_root = base
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(base);
+
+    let mut warnings = Vec::new();
+    let sandbox = project.sandbox.filter(|sandbox| {
+        let stricter = sandbox.rank() <= current.rank();
+        if !stricter {
+            warnings.push(format!(
+                "{} sets sandbox = \"{}\", which is looser than \"{}\"; ignoring it",
+                path.display(),
+                sandbox.as_str(),
+                current.as_str()
+            ));
+        }
+        stricter
+    });
+    let writable_roots = project.writable_roots.map(|roots| {
+        roots
+            .into_iter()
+            .map(|root| normalize_path(&base.join(root)))
+            .filter(|root| {
+                let inside = is_within(root, repo_root);
+                if !inside {
+                    warnings.push(format!(
+                        "{} lists writable root {} outside the repository; ignoring it",
+                        path.display(),
+                        root.display()
+                    ));
+                }
+                inside
+            })
+            .collect()
+    });
+    let fm = Frontmatter {
+        model: project.model,
+        sandbox,
+        writable_roots,
+        instructions_append: project.instructions_append,
+        ..Frontmatter::default()
+    };
+    Ok((fm, warnings))
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Whether `path` is `root` or below it, following symlinks where the
+/// paths exist.
+fn is_within(path: &Path, root: &Path) -> bool {
+    let root = normalize_path(root);
+    if !path.starts_with(&root) {
+        return false;
+    }
+    match (std::fs::canonicalize(path), std::fs::canonicalize(&root)) {
+        (Ok(path), Ok(root)) => path.starts_with(root),
+        _ => true,
+    }
+}
+
+/// Price of a model in USD per million tokens, from the `[pricing]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    /// Uncached input tokens.
+    pub input: f64,
+    /// Output tokens, reasoning included.
+    pub output: f64,
+    /// Cached input tokens (default: the `input` price).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+}
+
+impl ModelPrice {
+    /// Estimated cost in USD of the given token usage.
+    pub fn cost(&self, tokens: &TokenSnapshot) -> f64 {
+        let cached = tokens.cached_input_tokens.max(0) as f64;
+        let uncached = (tokens.input_tokens - tokens.cached_input_tokens).max(0) as f64;
+        let output = tokens.output_tokens.max(0) as f64;
+        (uncached * self.input
+            + cached * self.cached_input.unwrap_or(self.input)
+            + output * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// How the `merge` completion action lands the bot branch on the base branch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Fast-forward only; fails if the base branch has moved.
+    #[default]
+    FfOnly,
+    /// Allow a merge commit when the branches have diverged.
+    Merge,
+    /// Rebase the bot branch onto the base branch, then fast-forward.
+    Rebase,
+}
+
+impl MergeStrategy {
+    /// The config.md spelling of this strategy.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MergeStrategy::FfOnly => "ff-only",
+            MergeStrategy::Merge => "merge",
+            MergeStrategy::Rebase => "rebase",
+        }
+    }
+}
+
+/// What the agent's commands may touch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sandbox {
+    /// Read files only.
+    ReadOnly,
+    /// Read anywhere, write inside the workspace.
+    #[default]
+    WorkspaceWrite,
+    /// No restrictions.
+    DangerFullAccess,
+}
+
+impl Sandbox {
+    /// Order from strictest (`read-only`) to loosest.
+    fn rank(self) -> u8 {
+        match self {
+            Sandbox::ReadOnly => 0,
+            Sandbox::WorkspaceWrite => 1,
+            Sandbox::DangerFullAccess => 2,
+        }
+    }
+
+    /// The config.md spelling of this mode.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sandbox::ReadOnly => "read-only",
+            Sandbox::WorkspaceWrite => "workspace-write",
+            Sandbox::DangerFullAccess => "danger-full-access",
+        }
+    }
 }
 
 /// Runtime configuration for a bot run.
 /// Loaded from the bot's `config.md` (TOML frontmatter + markdown body).
-#[derive(Debug, Clone)]
+///
+/// The serde derives give a JSON form with the same field names as the
+/// frontmatter keys (plus `instructions`); missing fields take the built-in
+/// defaults. `config.md` stays the on-disk format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BotConfig {
     /// Short description of the bot.
     pub description: String,
@@ -110,10 +499,50 @@ pub struct BotConfig {
     pub stop_phrase: Option<String>,
     /// Model override.
     pub model: Option<String>,
-    /// Sandbox mode: "read-only", "workspace-write", or "danger-full-access".
-    pub sandbox: String,
+    /// Sandbox mode for the agent's commands.
+    pub sandbox: Sandbox,
     /// If true, skip the git repository requirement.
     pub skip_git_check: bool,
+    /// Initialize submodules in new worktrees (`None` = only if `.gitmodules` exists).
+    pub init_submodules: Option<bool>,
+    /// How the `merge` action integrates the bot branch.
+    pub merge_strategy: MergeStrategy,
+    /// Commit leftover worktree changes before the completion action runs.
+    pub auto_commit: bool,
+    /// Maximum memory entries before the oldest are evicted (`0` means unlimited).
+    pub max_memory_entries: usize,
+    /// Maximum bytes per memory value before truncation (`0` means unlimited).
+    pub max_memory_value_bytes: usize,
+    /// Iteration records kept in memory history (`0` means unlimited).
+    pub max_memory_history: usize,
+    /// Byte budget for the prompt's memory section (`0` means unlimited).
+    pub max_prompt_memory_bytes: usize,
+    /// Recent sessions summarized in the prompt (`0` leaves the section out).
+    pub prompt_history_count: usize,
+    /// Explain in the prompt how to author new skills (always off in a read-only sandbox).
+    pub prompt_teach_skills: bool,
+    /// Add a `## Project Layout` file tree of the working directory to the prompt.
+    pub include_file_tree: bool,
+    /// Only load skills tagged with at least one of these (empty loads all).
+    pub skill_tags: Vec<String>,
+    /// Stream the model's reasoning and record it in the session history.
+    pub show_reasoning: bool,
+    /// Webhook that receives a JSON POST when the run finishes.
+    pub notify_url: Option<String>,
+    /// Template for the webhook body with `{{key}}` placeholders (default: the raw payload).
+    pub notify_body: Option<String>,
+    /// Fail to load when a `${VAR}` reference is unset, instead of expanding it to empty.
+    pub strict_env: bool,
+    /// Worktree branch name with `{bot}`, `{slug}`, `{n}`, `{date}`, `{ts}` placeholders.
+    pub branch_template: Option<String>,
+    /// Workspace to use when the working directory's workspace has no history.
+    pub default_project: Option<String>,
+    /// Extra directories the agent may write to in the `workspace-write` sandbox.
+    pub writable_roots: Vec<PathBuf>,
+    /// Text appended to the instructions, e.g. from a project's `.openbot.toml`.
+    pub instructions_append: Option<String>,
+    /// Per-model prices for the cost estimate in the run summary.
+    pub pricing: BTreeMap<String, ModelPrice>,
 }
 
 impl Default for BotConfig {
@@ -125,8 +554,28 @@ impl Default for BotConfig {
             sleep_secs: 30,
             stop_phrase: Some("TASK COMPLETE".into()),
             model: None,
-            sandbox: "workspace-write".into(),
+            sandbox: Sandbox::default(),
             skip_git_check: false,
+            init_submodules: None,
+            merge_strategy: MergeStrategy::default(),
+            auto_commit: true,
+            max_memory_entries: 200,
+            max_memory_value_bytes: 4096,
+            max_memory_history: 50,
+            max_prompt_memory_bytes: 8192,
+            prompt_history_count: 5,
+            prompt_teach_skills: true,
+            include_file_tree: false,
+            skill_tags: Vec::new(),
+            show_reasoning: false,
+            notify_url: None,
+            notify_body: None,
+            strict_env: false,
+            branch_template: None,
+            default_project: None,
+            writable_roots: Vec::new(),
+            instructions_append: None,
+            pricing: BTreeMap::new(),
         }
     }
 }
@@ -134,10 +583,22 @@ impl Default for BotConfig {
 /// Parse a config.md file into (frontmatter, body).
 /// Frontmatter is delimited by `+++` lines.
 fn parse_config_md(contents: &str) -> Result<(Frontmatter, String)> {
-    let trimmed = contents.trim_start();
-    if !trimmed.starts_with("+++") {
+    let Some((frontmatter_str, body)) = split_config_md(contents)? else {
         // No frontmatter -- entire file is instructions.
         return Ok((Frontmatter::default(), contents.trim().to_string()));
+    };
+
+    let frontmatter: Frontmatter =
+        toml::from_str(frontmatter_str).with_context(|| "parsing config.md frontmatter")?;
+
+    Ok((frontmatter, body))
+}
+
+/// Split config.md into raw frontmatter and trimmed body, or `None` without frontmatter.
+fn split_config_md(contents: &str) -> Result<Option<(&str, String)>> {
+    let trimmed = contents.trim_start();
+    if !trimmed.starts_with("+++") {
+        return Ok(None);
     }
 
     // Find the closing +++.
@@ -155,10 +616,124 @@ fn parse_config_md(contents: &str) -> Result<(Frontmatter, String)> {
         String::new()
     };
 
-    let frontmatter: Frontmatter =
+    Ok(Some((frontmatter_str, body)))
+}
+
+/// Every frontmatter key config.md understands.
+fn frontmatter_keys() -> Vec<String> {
+    // Unset options serialize as nulls, so every field name shows up.
+    match serde_json::to_value(Frontmatter::default()) {
+        Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Find problems in a config.md that [`BotConfig::load`] would silently accept.
+///
+/// Returns one message per unknown key, mistyped value, or out-of-range
+/// value. Errors only when the file can't be parsed at all.
+pub fn validate_config_md(contents: &str) -> Result<Vec<String>> {
+    let Some((frontmatter_str, body)) = split_config_md(contents)? else {
+        return Ok(Vec::new());
+    };
+    let table: toml::Table =
         toml::from_str(frontmatter_str).with_context(|| "parsing config.md frontmatter")?;
 
-    Ok((frontmatter, body))
+    let known = frontmatter_keys();
+    let mut problems = Vec::new();
+    let mut valid = toml::Table::new();
+    for (key, value) in table {
+        if !known.contains(&key) {
+            let hint = known
+                .iter()
+                .map(|k| (edit_distance(k, &key), k))
+                .filter(|(d, _)| *d <= 2)
+                .min();
+            problems.push(match hint {
+                Some((_, k)) => format!("unknown key `{key}` (did you mean `{k}`?)"),
+                None => format!("unknown key `{key}`"),
+            });
+            continue;
+        }
+        // Check each key alone so one bad value doesn't hide the others.
+        let mut single = toml::Table::new();
+        single.insert(key.clone(), value.clone());
+        match toml::Value::Table(single).try_into::<Frontmatter>() {
+            Ok(_) => {
+                valid.insert(key, value);
+            }
+            Err(e) => problems.push(format!("`{key}`: {}", e.message().trim())),
+        }
+    }
+
+    let fm: Frontmatter = toml::Value::Table(valid)
+        .try_into()
+        .with_context(|| "parsing config.md frontmatter")?;
+    if let Some(secs) = fm.sleep_secs.filter(|s| *s > 86_400) {
+        problems.push(format!("`sleep_secs` = {secs} is more than a day"));
+    }
+    if let Some(ref url) = fm.notify_url {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            problems.push(format!("`notify_url` = {url:?} is not an http(s) URL"));
+        }
+    }
+    if fm.notify_body.is_some() && fm.notify_url.is_none() {
+        problems.push("`notify_body` is set but `notify_url` is not".to_string());
+    }
+    if body.is_empty() {
+        problems.push(
+            "no instructions after the frontmatter; the default instructions are used".to_string(),
+        );
+    }
+    Ok(problems)
+}
+
+/// Replace `${VAR}` in `text` with `lookup(VAR)`, and `$$` with `$`.
+///
+/// Unset variables expand to empty and their names are pushed to `missing`.
+/// Any other `$`, including an unterminated `${`, is kept as written.
+fn expand_env_vars(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    missing: &mut Vec<String>,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(close) = after.strip_prefix('{').and_then(|a| a.find('}')) {
+            let name = &after[1..close + 1];
+            match lookup(name) {
+                Some(value) => out.push_str(&value),
+                None => missing.push(name.to_string()),
+            }
+            rest = &after[close + 2..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Levenshtein distance, for "did you mean" hints.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            cur.push(substitute.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
 }
 
 /// Serialize a BotConfig back to config.md format.
@@ -186,11 +761,107 @@ pub fn serialize_config_md(config: &BotConfig) -> String {
         fm.push_str(&format!("model = {:?}\n", model));
     }
     if config.sandbox != defaults.sandbox {
-        fm.push_str(&format!("sandbox = {:?}\n", config.sandbox));
+        fm.push_str(&format!("sandbox = {:?}\n", config.sandbox.as_str()));
     }
     if config.skip_git_check {
         fm.push_str("skip_git_check = true\n");
     }
+    if let Some(init) = config.init_submodules {
+        fm.push_str(&format!("init_submodules = {init}\n"));
+    }
+    if config.merge_strategy != defaults.merge_strategy {
+        fm.push_str(&format!(
+            "merge_strategy = {:?}\n",
+            config.merge_strategy.as_str()
+        ));
+    }
+
+    if !config.auto_commit {
+        fm.push_str("auto_commit = false\n");
+    }
+    if config.max_memory_entries != defaults.max_memory_entries {
+        fm.push_str(&format!(
+            "max_memory_entries = {}\n",
+            config.max_memory_entries
+        ));
+    }
+    if config.max_memory_value_bytes != defaults.max_memory_value_bytes {
+        fm.push_str(&format!(
+            "max_memory_value_bytes = {}\n",
+            config.max_memory_value_bytes
+        ));
+    }
+    if config.max_memory_history != defaults.max_memory_history {
+        fm.push_str(&format!(
+            "max_memory_history = {}\n",
+            config.max_memory_history
+        ));
+    }
+    if config.max_prompt_memory_bytes != defaults.max_prompt_memory_bytes {
+        fm.push_str(&format!(
+            "max_prompt_memory_bytes = {}\n",
+            config.max_prompt_memory_bytes
+        ));
+    }
+    if config.prompt_history_count != defaults.prompt_history_count {
+        fm.push_str(&format!(
+            "prompt_history_count = {}\n",
+            config.prompt_history_count
+        ));
+    }
+    if !config.prompt_teach_skills {
+        fm.push_str("prompt_teach_skills = false\n");
+    }
+    if config.include_file_tree {
+        fm.push_str("include_file_tree = true\n");
+    }
+    if !config.skill_tags.is_empty() {
+        let tags: Vec<String> = config.skill_tags.iter().map(|t| format!("{t:?}")).collect();
+        fm.push_str(&format!("skill_tags = [{}]\n", tags.join(", ")));
+    }
+    if config.show_reasoning {
+        fm.push_str("show_reasoning = true\n");
+    }
+    if let Some(ref url) = config.notify_url {
+        fm.push_str(&format!("notify_url = {url:?}\n"));
+    }
+    if let Some(ref body) = config.notify_body {
+        fm.push_str(&format!("notify_body = {body:?}\n"));
+    }
+    if config.strict_env {
+        fm.push_str("strict_env = true\n");
+    }
+    if let Some(ref template) = config.branch_template {
+        fm.push_str(&format!("branch_template = {template:?}\n"));
+    }
+    if let Some(ref project) = config.default_project {
+        fm.push_str(&format!("default_project = {project:?}\n"));
+    }
+    if !config.writable_roots.is_empty() {
+        let roots: Vec<String> = config
+            .writable_roots
+            .iter()
+            .map(|r| format!("{:?}", r.display().to_string()))
+            .collect();
+        fm.push_str(&format!("writable_roots = [{}]\n", roots.join(", ")));
+    }
+    if let Some(ref append) = config.instructions_append {
+        fm.push_str(&format!("instructions_append = {append:?}\n"));
+    }
+    // Tables go last so the keys above stay top-level.
+    if !config.pricing.is_empty() {
+        fm.push_str("\n[pricing]\n");
+        for (model, price) in &config.pricing {
+            fm.push_str(&format!(
+                "{model:?} = {{ input = {:?}, output = {:?}",
+                price.input, price.output
+            ));
+            if let Some(cached) = price.cached_input {
+                fm.push_str(&format!(", cached_input = {cached:?}"));
+            }
+            fm.push_str(" }\n");
+        }
+    }
 
     fm.push_str("\n+++\n\n");
     fm.push_str(&config.instructions);
@@ -199,31 +870,123 @@ pub fn serialize_config_md(config: &BotConfig) -> String {
 }
 
 impl BotConfig {
-    /// Load config for a bot. Falls back to defaults if no config.md exists.
+    /// Load config for a bot.
+    ///
+    /// Each setting comes from the bot's config.md if set there, else from
+    /// the global `~/.openbot/config.md`, else from the built-in default.
+    /// A project's `.openbot.toml` is not read; see [`Self::load_for_project`].
     pub fn load(bot_name: &str) -> Result<Self> {
+        Self::load_layered(bot_name, load_global_defaults()?, Frontmatter::default())
+    }
+
+    /// Load config for a bot run started in `cwd`: like [`Self::load`], with
+    /// the `.openbot.toml` that applies to `cwd` layered between the global
+    /// and the bot's config.md.
+    pub fn load_for_project(bot_name: &str, cwd: &Path) -> Result<Self> {
+        let global = load_global_defaults()?;
+        let (project, warnings) = load_project_defaults(cwd, global.sandbox.unwrap_or_default())?;
+        for warning in warnings {
+            eprintln!("warning: {warning}");
+        }
+        Self::load_layered(bot_name, global, project)
+    }
+
+    /// Load the bot's config.md over `project` over `global`.
+    fn load_layered(bot_name: &str, global: Frontmatter, project: Frontmatter) -> Result<Self> {
         let config_path = bot_config_path(bot_name)?;
-        if config_path.exists() {
+        let (fm, body) = if config_path.exists() {
             let contents = std::fs::read_to_string(&config_path)
                 .with_context(|| format!("reading {}", config_path.display()))?;
-            let (fm, body) = parse_config_md(&contents)?;
-
-            let defaults = Self::default();
-            Ok(Self {
-                description: fm.description.unwrap_or_default(),
-                instructions: if body.is_empty() {
-                    defaults.instructions
-                } else {
-                    body
-                },
-                max_iterations: fm.max_iterations.unwrap_or(defaults.max_iterations),
-                sleep_secs: fm.sleep_secs.unwrap_or(defaults.sleep_secs),
-                stop_phrase: fm.stop_phrase.or(defaults.stop_phrase),
-                model: fm.model,
-                sandbox: fm.sandbox.unwrap_or(defaults.sandbox),
-                skip_git_check: fm.skip_git_check.unwrap_or(defaults.skip_git_check),
-            })
+            parse_config_md(&contents)?
         } else {
-            Ok(Self::default())
+            (Frontmatter::default(), String::new())
+        };
+        let mut config =
+            Self::from_frontmatter(fm.layered_over(project.layered_over(global)), body);
+        config.expand_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    /// Expand `${VAR}` references in the instructions and string settings.
+    ///
+    /// Unset variables expand to empty with a warning, or fail the load when
+    /// `strict_env` is set.
+    fn expand_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let mut missing = Vec::new();
+        let values = [&mut self.description, &mut self.instructions]
+            .into_iter()
+            .chain(self.stop_phrase.as_mut())
+            .chain(self.model.as_mut())
+            .chain(self.notify_url.as_mut())
+            .chain(self.notify_body.as_mut())
+            .chain(self.instructions_append.as_mut());
+        for value in values {
+            *value = expand_env_vars(value, &lookup, &mut missing);
+        }
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        if self.strict_env {
+            anyhow::bail!(
+                "config.md references unset environment variable(s): {} (strict_env is on)",
+                missing.join(", ")
+            );
+        }
+        for name in &missing {
+            eprintln!(
+                "warning: config.md references unset environment variable {name}; using an empty value"
+            );
+        }
+        Ok(())
+    }
+
+    /// Build a config from parsed frontmatter and body, using built-in
+    /// defaults for anything unset.
+    fn from_frontmatter(fm: Frontmatter, body: String) -> Self {
+        let defaults = Self::default();
+        Self {
+            description: fm.description.unwrap_or_default(),
+            instructions: if body.is_empty() {
+                defaults.instructions
+            } else {
+                body
+            },
+            max_iterations: fm.max_iterations.unwrap_or(defaults.max_iterations),
+            sleep_secs: fm.sleep_secs.unwrap_or(defaults.sleep_secs),
+            stop_phrase: fm.stop_phrase.or(defaults.stop_phrase),
+            model: fm.model,
+            sandbox: fm.sandbox.unwrap_or(defaults.sandbox),
+            skip_git_check: fm.skip_git_check.unwrap_or(defaults.skip_git_check),
+            init_submodules: fm.init_submodules.or(defaults.init_submodules),
+            merge_strategy: fm.merge_strategy.unwrap_or(defaults.merge_strategy),
+            auto_commit: fm.auto_commit.unwrap_or(defaults.auto_commit),
+            max_memory_entries: fm.max_memory_entries.unwrap_or(defaults.max_memory_entries),
+            max_memory_value_bytes: fm
+                .max_memory_value_bytes
+                .unwrap_or(defaults.max_memory_value_bytes),
+            max_memory_history: fm.max_memory_history.unwrap_or(defaults.max_memory_history),
+            max_prompt_memory_bytes: fm
+                .max_prompt_memory_bytes
+                .unwrap_or(defaults.max_prompt_memory_bytes),
+            prompt_history_count: fm
+                .prompt_history_count
+                .unwrap_or(defaults.prompt_history_count),
+            prompt_teach_skills: fm
+                .prompt_teach_skills
+                .unwrap_or(defaults.prompt_teach_skills),
+            include_file_tree: fm.include_file_tree.unwrap_or(defaults.include_file_tree),
+            skill_tags: fm.skill_tags.unwrap_or(defaults.skill_tags),
+            show_reasoning: fm.show_reasoning.unwrap_or(defaults.show_reasoning),
+            notify_url: fm.notify_url,
+            notify_body: fm.notify_body,
+            strict_env: fm.strict_env.unwrap_or(defaults.strict_env),
+            branch_template: fm.branch_template,
+            default_project: fm.default_project,
+            writable_roots: fm.writable_roots.unwrap_or(defaults.writable_roots),
+            instructions_append: fm.instructions_append,
+            pricing: fm.pricing.unwrap_or(defaults.pricing),
         }
     }
 
@@ -235,6 +998,7 @@ impl BotConfig {
         model: Option<String>,
         skip_git_check: bool,
         sleep_secs: Option<u64>,
+        sandbox: Option<Sandbox>,
     ) -> Self {
         if let Some(prompt) = prompt {
             self.instructions = prompt;
@@ -251,15 +1015,20 @@ impl BotConfig {
         if let Some(s) = sleep_secs {
             self.sleep_secs = s;
         }
+        if let Some(sandbox) = sandbox {
+            self.sandbox = sandbox;
+        }
         self
     }
 
-    /// Convert sandbox string to codex SandboxMode.
+    /// Convert the sandbox setting to codex SandboxMode.
     pub fn sandbox_mode(&self) -> codex_protocol::config_types::SandboxMode {
-        match self.sandbox.as_str() {
-            "read-only" => codex_protocol::config_types::SandboxMode::ReadOnly,
-            "danger-full-access" => codex_protocol::config_types::SandboxMode::DangerFullAccess,
-            _ => codex_protocol::config_types::SandboxMode::WorkspaceWrite,
+        match self.sandbox {
+            Sandbox::ReadOnly => codex_protocol::config_types::SandboxMode::ReadOnly,
+            Sandbox::WorkspaceWrite => codex_protocol::config_types::SandboxMode::WorkspaceWrite,
+            Sandbox::DangerFullAccess => {
+                codex_protocol::config_types::SandboxMode::DangerFullAccess
+            }
         }
     }
 
@@ -268,8 +1037,370 @@ impl BotConfig {
         Ok(vec![global_skills_dir()?, bot_skills_dir(bot_name)?])
     }
 
+    /// Estimated cost in USD of `tokens` on `model`, if `[pricing]` lists it.
+    pub fn estimate_cost(&self, model: &str, tokens: &TokenSnapshot) -> Option<f64> {
+        self.pricing.get(model).map(|price| price.cost(tokens))
+    }
+
+    /// Memory caps derived from this config.
+    pub fn memory_limits(&self) -> MemoryLimits {
+        MemoryLimits {
+            max_entries: self.max_memory_entries,
+            max_value_bytes: self.max_memory_value_bytes,
+            max_history: self.max_memory_history,
+        }
+    }
+
     /// Return the memory path for this bot.
     pub fn memory_path(bot_name: &str) -> Result<PathBuf> {
         bot_memory_path(bot_name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    #[test]
+    fn validate_config_md_reports_typos_types_and_ranges() {
+        let contents = "+++\n\
+            max_iteration = 5\n\
+            sleep_secs = \"soon\"\n\
+            sandbox = \"read_only\"\n\
+            merge_strategy = \"squash\"\n\
+            wibble = true\n\
+            model = \"o3\"\n\
+            +++\n\nDo things.\n";
+        let problems = validate_config_md(contents).expect("parses");
+        assert_eq!(problems.len(), 5, "{problems:#?}");
+        assert!(
+            problems.contains(
+                &"unknown key `max_iteration` (did you mean `max_iterations`?)".to_string()
+            )
+        );
+        assert!(problems.contains(&"unknown key `wibble`".to_string()));
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("`sleep_secs`: invalid type"))
+        );
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("`merge_strategy`: unknown variant"))
+        );
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.starts_with("`sandbox`: unknown variant `read_only`"))
+        );
+
+        let clean = "+++\nmax_iterations = 3\n+++\n\nDo things.\n";
+        assert!(validate_config_md(clean).expect("parses").is_empty());
+        assert!(validate_config_md("+++\nmax_iterations = 3\n").is_err());
+    }
+
+    #[test]
+    fn sandbox_parses_valid_modes_and_rejects_typos() {
+        for mode in [
+            Sandbox::ReadOnly,
+            Sandbox::WorkspaceWrite,
+            Sandbox::DangerFullAccess,
+        ] {
+            let contents = format!("+++\nsandbox = {:?}\n+++\n", mode.as_str());
+            let (fm, _) = parse_config_md(&contents).expect("valid mode");
+            assert_eq!(fm.sandbox, Some(mode));
+        }
+
+        let err = parse_config_md("+++\nsandbox = \"read_only\"\n+++\n").expect_err("typo");
+        let message = format!("{err:#}");
+        assert!(
+            message.contains("`read-only`, `workspace-write`, `danger-full-access`"),
+            "{message}"
+        );
+
+        let config = BotConfig {
+            sandbox: Sandbox::ReadOnly,
+            ..BotConfig::default()
+        };
+        assert!(serialize_config_md(&config).contains("sandbox = \"read-only\"\n"));
+    }
+
+    #[test]
+    fn validate_bot_name_accepts_portable_names() {
+        for name in [
+            "mybot",
+            "security-bot",
+            "test_bot.v2",
+            "Bot42",
+            &"a".repeat(64),
+        ] {
+            assert!(
+                validate_bot_name(name).is_ok(),
+                "expected {name:?} to be valid"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_bot_name_rejects_unsafe_names() {
+        for name in [
+            "",
+            ".",
+            "..",
+            "../evil",
+            "a/b",
+            "a\\b",
+            ".hidden",
+            "-flag",
+            "has space",
+            "emoji🤖",
+            "nul\0",
+            &"a".repeat(65),
+        ] {
+            assert!(
+                validate_bot_name(name).is_err(),
+                "expected {name:?} to be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn openbot_home_override_wins() {
+        let resolved = resolve_openbot_home(
+            Some(PathBuf::from("/custom/openbot")),
+            Some(PathBuf::from("/home/me")),
+            Some(PathBuf::from("/home/me/.local/share")),
+        );
+        assert_eq!(resolved, Some(PathBuf::from("/custom/openbot")));
+    }
+
+    #[test]
+    fn openbot_home_ignores_empty_override() {
+        let resolved = resolve_openbot_home(
+            Some(PathBuf::new()),
+            Some(PathBuf::from("/nonexistent-home")),
+            None,
+        );
+        assert_eq!(resolved, Some(PathBuf::from("/nonexistent-home/.openbot")));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn openbot_home_defaults_to_dot_openbot() {
+        let resolved = resolve_openbot_home(
+            None,
+            Some(PathBuf::from("/nonexistent-home")),
+            Some(PathBuf::from("/nonexistent-home/.local/share")),
+        );
+        assert_eq!(resolved, Some(PathBuf::from("/nonexistent-home/.openbot")));
+    }
+
+    #[test]
+    fn openbot_home_falls_back_to_data_dir() {
+        let resolved = resolve_openbot_home(None, None, Some(PathBuf::from("/data")));
+        assert_eq!(resolved, Some(PathBuf::from("/data/openbot")));
+    }
+
+    #[test]
+    fn config_layers_cli_over_bot_over_global_over_builtin() {
+        let (global, _) = parse_config_md(
+            "+++\nmodel = \"global-model\"\nsandbox = \"read-only\"\nsleep_secs = 5\n+++\n",
+        )
+        .unwrap();
+        let (bot, body) =
+            parse_config_md("+++\nmodel = \"bot-model\"\n+++\n\nBot instructions.\n").unwrap();
+        let config = BotConfig::from_frontmatter(bot.layered_over(global), body);
+        let defaults = BotConfig::default();
+
+        // Built-in default: set nowhere else.
+        assert_eq!(config.max_iterations, defaults.max_iterations);
+        // Global default: the bot leaves it unset.
+        assert_eq!(config.sandbox, Sandbox::ReadOnly);
+        assert_eq!(config.sleep_secs, 5);
+        // Bot config beats the global default.
+        assert_eq!(config.model.as_deref(), Some("bot-model"));
+        assert_eq!(config.instructions, "Bot instructions.");
+
+        // CLI overrides beat everything.
+        let config =
+            config.with_overrides(None, None, Some("cli-model".into()), false, Some(0), None);
+        assert_eq!(config.model.as_deref(), Some("cli-model"));
+        assert_eq!(config.sleep_secs, 0);
+        assert_eq!(config.sandbox, Sandbox::ReadOnly);
+    }
+
+    #[test]
+    fn expand_env_vars_substitutes_escapes_and_reports_missing() {
+        let lookup = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+        let mut missing = Vec::new();
+        assert_eq!(
+            expand_env_vars(
+                "cd ${HOME}/src; echo $$${HOME} $PATH ${NOPE}x ${",
+                lookup,
+                &mut missing
+            ),
+            "cd /home/me/src; echo $/home/me $PATH x ${"
+        );
+        assert_eq!(missing, vec!["NOPE"]);
+        assert_eq!(expand_env_vars("$${HOME}", lookup, &mut missing), "${HOME}");
+
+        let mut config = BotConfig {
+            instructions: "Token: ${NOPE}".into(),
+            strict_env: true,
+            ..BotConfig::default()
+        };
+        let err = config.expand_env(lookup).unwrap_err();
+        assert!(err.to_string().contains("NOPE"), "{err}");
+    }
+
+    #[test]
+    fn pricing_merges_per_model_and_estimates_cost() {
+        let (global, _) = parse_config_md(
+            "+++\n[pricing]\n\"gpt-5\" = { input = 1.25, output = 10.0, cached_input = 0.125 }\n\"o3\" = { input = 2.0, output = 8.0 }\n+++\n",
+        )
+        .unwrap();
+        let (bot, body) =
+            parse_config_md("+++\n[pricing]\n\"o3\" = { input = 1.0, output = 4.0 }\n+++\n")
+                .unwrap();
+        let config = BotConfig::from_frontmatter(bot.layered_over(global), body);
+        assert_eq!(config.pricing.len(), 2);
+        assert_eq!(config.pricing["o3"].input, 1.0);
+
+        let tokens = TokenSnapshot {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 200_000,
+            output_tokens: 100_000,
+            ..Default::default()
+        };
+        // 0.8M * 1.25 + 0.2M * 0.125 + 0.1M * 10 = 1.0 + 0.025 + 1.0
+        let cost = config.estimate_cost("gpt-5", &tokens).unwrap();
+        assert!((cost - 2.025).abs() < 1e-9, "{cost}");
+        assert_eq!(config.estimate_cost("unpriced", &tokens), None);
+
+        let (round_trip, _) = parse_config_md(&serialize_config_md(&config)).unwrap();
+        assert_eq!(round_trip.pricing.as_ref(), Some(&config.pricing));
+    }
+
+    #[test]
+    fn config_md_and_serde_round_trips_agree() {
+        // No `..Default::default()`: a new field must be added here too.
+        let config = BotConfig {
+            description: "Keeps \"main\" green".into(),
+            instructions: "Fix failing tests.\n\nCommit each fix.".into(),
+            max_iterations: 3,
+            sleep_secs: 5,
+            stop_phrase: Some("ALL DONE".into()),
+            model: Some("o3".into()),
+            sandbox: Sandbox::ReadOnly,
+            skip_git_check: true,
+            init_submodules: Some(false),
+            merge_strategy: MergeStrategy::Rebase,
+            auto_commit: false,
+            max_memory_entries: 10,
+            max_memory_value_bytes: 100,
+            max_memory_history: 0,
+            max_prompt_memory_bytes: 2048,
+            prompt_history_count: 0,
+            prompt_teach_skills: false,
+            include_file_tree: true,
+            skill_tags: vec!["rust".into(), "ci".into()],
+            show_reasoning: true,
+            notify_url: Some("https://hooks.example.com/x".into()),
+            notify_body: Some("{\"text\": \"{{summary}}\"}\n".into()),
+            strict_env: true,
+            branch_template: Some("bots/{bot}-{n}".into()),
+            default_project: Some("api".into()),
+            writable_roots: vec![PathBuf::from("/tmp/cache")],
+            instructions_append: Some("Run `make lint` first.".into()),
+            pricing: BTreeMap::from([(
+                "o3".to_string(),
+                ModelPrice {
+                    input: 2.0,
+                    output: 8.0,
+                    cached_input: Some(0.5),
+                },
+            )]),
+        };
+        let expected = serde_json::to_value(&config).unwrap();
+
+        let (fm, body) = parse_config_md(&serialize_config_md(&config)).unwrap();
+        let from_md = BotConfig::from_frontmatter(fm, body);
+        assert_eq!(serde_json::to_value(&from_md).unwrap(), expected);
+
+        let from_json: BotConfig = serde_json::from_value(expected.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&from_json).unwrap(), expected);
+
+        let defaults: BotConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(
+            serde_json::to_value(&defaults).unwrap(),
+            serde_json::to_value(BotConfig::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn project_config_is_found_within_the_repo_and_layers_below_the_bot() {
+        let outer = TempDir::new("project-config");
+        let repo = outer.join("repo");
+        let nested = repo.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+        std::fs::write(outer.join(PROJECT_CONFIG_FILE), "model = \"outside\"\n").unwrap();
+
+        // A file above the repository root doesn't apply.
+        assert_eq!(find_project_config(&nested), None);
+
+        std::fs::write(
+            repo.join(PROJECT_CONFIG_FILE),
+            "model = \"gpt-5\"\n\
+             sandbox = \"read-only\"\n\
+             writable_roots = [\"cache\", \"../../outer\", \"/opt/shared\"]\n\
+             instructions_append = \"Run `make lint` first.\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(repo.join(PROJECT_CONFIG_FILE))
+        );
+
+        let (project, warnings) = load_project_defaults(&nested, Sandbox::default()).unwrap();
+        // Roots outside the repository are dropped.
+        assert_eq!(warnings.len(), 2, "{warnings:#?}");
+        let global = parse_config_md("+++\nmodel = \"o3\"\nsleep_secs = 1\n+++\n")
+            .unwrap()
+            .0;
+        let (bot, body) = parse_config_md("+++\nsandbox = \"workspace-write\"\n+++\n").unwrap();
+        let config =
+            BotConfig::from_frontmatter(bot.layered_over(project.layered_over(global)), body);
+        assert_eq!(config.model.as_deref(), Some("gpt-5"));
+        assert_eq!(config.sandbox, Sandbox::WorkspaceWrite);
+        assert_eq!(config.sleep_secs, 1);
+        assert_eq!(config.writable_roots, vec![repo.join("cache")]);
+        assert_eq!(
+            config.instructions_append.as_deref(),
+            Some("Run `make lint` first.")
+        );
+
+        // The repository can tighten the sandbox but not loosen it.
+        std::fs::write(
+            repo.join(PROJECT_CONFIG_FILE),
+            "sandbox = \"danger-full-access\"\n",
+        )
+        .unwrap();
+        let (project, warnings) = load_project_defaults(&nested, Sandbox::default()).unwrap();
+        assert_eq!(project.sandbox, None);
+        assert!(warnings[0].contains("looser"), "{warnings:#?}");
+        let (project, warnings) =
+            load_project_defaults(&nested, Sandbox::DangerFullAccess).unwrap();
+        assert_eq!(project.sandbox, Some(Sandbox::DangerFullAccess));
+        assert!(warnings.is_empty());
+
+        std::fs::write(repo.join(PROJECT_CONFIG_FILE), "modle = \"o3\"\n").unwrap();
+        let err = load_project_defaults(&nested, Sandbox::default()).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unknown field `modle`"),
+            "{err:#}"
+        );
+    }
+}
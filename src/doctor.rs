@@ -0,0 +1,202 @@
+//! `openbot doctor`: one pass over everything a run depends on, with a hint
+//! for each failure.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::registry::{OfflineError, Registry};
+
+/// Outcome of a single check.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    Fail,
+    Skip,
+}
+
+/// One line of the report.
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+    detail: String,
+    /// What to do about a failure.
+    hint: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Fail,
+            detail: detail.into(),
+            hint: Some(hint),
+        }
+    }
+
+    fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Skip,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+}
+
+/// Run every check, print the report, and error if any check failed.
+pub async fn run(offline: bool) -> Result<()> {
+    let mut checks = Vec::new();
+
+    match crate::config::openbot_home() {
+        Ok(home) => {
+            checks.push(Check::pass("openbot home", home.display().to_string()));
+            checks.push(check_writable(&home));
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "openbot home",
+                format!("{e:#}"),
+                "set HOME, or OPENBOT_HOME to choose a data directory",
+            ));
+            checks.push(Check::skip("openbot home writable", "no openbot home"));
+        }
+    }
+
+    checks.push(check_git());
+
+    match codex_core::config::find_codex_home() {
+        Ok(dir) => checks.push(Check::pass("codex home", dir.display().to_string())),
+        Err(e) => checks.push(Check::fail(
+            "codex home",
+            e.to_string(),
+            "set CODEX_HOME, or HOME so ~/.codex can be found",
+        )),
+    }
+
+    checks.push(check_auth().await);
+    checks.push(check_registry(offline).await);
+
+    print!("{}", render(&checks));
+    let failed = checks.iter().filter(|c| c.outcome == Outcome::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
+/// Create the directory if needed and write and remove a probe file in it.
+fn check_writable(home: &Path) -> Check {
+    let name = "openbot home writable";
+    let probe = home.join(format!(".doctor-{}", std::process::id()));
+    let result = std::fs::create_dir_all(home)
+        .and_then(|()| std::fs::write(&probe, b"ok"))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check::pass(name, "yes"),
+        Err(e) => Check::fail(
+            name,
+            e.to_string(),
+            "fix the directory's permissions, or point OPENBOT_HOME somewhere writable",
+        ),
+    }
+}
+
+fn check_git() -> Check {
+    let output = std::process::Command::new("git").arg("--version").output();
+    match output {
+        Ok(out) if out.status.success() => Check::pass(
+            "git",
+            String::from_utf8_lossy(&out.stdout).trim().to_string(),
+        ),
+        Ok(out) => Check::fail(
+            "git",
+            String::from_utf8_lossy(&out.stderr).trim().to_string(),
+            "reinstall git",
+        ),
+        Err(e) => Check::fail(
+            "git",
+            e.to_string(),
+            "install git and put it on PATH (or run with --skip-git-check --no-worktree)",
+        ),
+    }
+}
+
+async fn check_auth() -> Check {
+    let name = "codex auth";
+    match crate::auth::load_auth_manager().await {
+        Ok((_, auth_manager)) if auth_manager.auth().await.is_some() => {
+            Check::pass(name, "credentials found")
+        }
+        Ok(_) => Check::fail(name, "not logged in", crate::auth::LOGIN_HINT),
+        Err(e) => Check::fail(name, format!("{e:#}"), crate::auth::LOGIN_HINT),
+    }
+}
+
+async fn check_registry(offline: bool) -> Check {
+    let name = "skills registry";
+    let result = match Registry::new(offline) {
+        Ok(registry) => registry.ping().await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(url) => Check::pass(name, format!("{url} reachable")),
+        Err(e) if e.is::<OfflineError>() => Check::skip(name, e.to_string()),
+        Err(e) => Check::fail(
+            name,
+            format!("{e:#}"),
+            "check your network or proxy; OPENBOT_REGISTRY_URL points at a mirror",
+        ),
+    }
+}
+
+/// Format the report: one aligned line per check, hints indented below.
+fn render(checks: &[Check]) -> String {
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for check in checks {
+        let mark = match check.outcome {
+            Outcome::Pass => "ok  ",
+            Outcome::Fail => "FAIL",
+            Outcome::Skip => "skip",
+        };
+        out.push_str(&format!(
+            "{mark}  {:<width$}  {}\n",
+            check.name, check.detail
+        ));
+        if let Some(hint) = check.hint {
+            out.push_str(&format!("      {:<width$}  hint: {hint}\n", ""));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_aligns_checks_and_shows_hints_for_failures() {
+        let checks = [
+            Check::pass("git", "git version 2.43.0"),
+            Check::fail("codex auth", "not logged in", "run `codex login`"),
+            Check::skip("skills registry", "offline mode"),
+        ];
+        let expected = [
+            "ok    git              git version 2.43.0",
+            "FAIL  codex auth       not logged in",
+            "                       hint: run `codex login`",
+            "skip  skills registry  offline mode",
+        ];
+        assert_eq!(render(&checks), expected.join("\n") + "\n");
+    }
+}
@@ -4,6 +4,83 @@ use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Directory under the repo root where openbot places its worktrees.
+const WORKTREE_DIR: &str = ".git/openbot-worktrees";
+
+/// Prefix of every branch openbot creates for a run.
+const BRANCH_PREFIX: &str = "openbot/";
+
+/// Tries at `git worktree add` when the branch or directory already exists.
+const WORKTREE_ADD_ATTEMPTS: usize = 3;
+
+/// Lock reason marking a worktree kept by `openbot run --keep-worktree`.
+const KEEP_REASON: &str = "kept by openbot run --keep-worktree";
+
+/// Branch name used when a bot sets no `branch_template`.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "openbot/{bot}-{ts}";
+
+/// Values substituted into a `branch_template`.
+pub struct BranchVars<'a> {
+    /// Bot name (`{bot}`).
+    pub bot: &'a str,
+    /// Project workspace slug (`{slug}`).
+    pub slug: &'a str,
+    /// Number of the run's first session in the workspace (`{n}`).
+    pub session: usize,
+}
+
+/// Render a branch name from `template`, then sanitize it into a valid ref.
+///
+/// Placeholders: `{bot}`, `{slug}`, `{n}`, `{date}` (UTC `YYYY-MM-DD`), and
+/// `{ts}` (Unix seconds). Unknown placeholders are left as written.
+pub fn branch_name(template: &str, vars: &BranchVars<'_>) -> String {
+    let now = chrono::Utc::now();
+    let rendered = template
+        .replace("{bot}", vars.bot)
+        .replace("{slug}", vars.slug)
+        .replace("{n}", &vars.session.to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{ts}", &now.timestamp().to_string());
+    let branch = sanitize_ref(&rendered);
+    if branch.is_empty() {
+        sanitize_ref(&format!("{BRANCH_PREFIX}{}-{}", vars.bot, now.timestamp()))
+    } else {
+        branch
+    }
+}
+
+/// Make `name` a valid branch name per `git check-ref-format`: invalid
+/// characters become `-`, and empty, dot-leading, and `.lock` components are
+/// fixed up.
+fn sanitize_ref(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(c) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let mut cleaned = cleaned.replace("@{", "-");
+    // One pass turns "..." into "..", so repeat until none are left.
+    while cleaned.contains("..") {
+        cleaned = cleaned.replace("..", ".");
+    }
+    let components: Vec<String> = cleaned
+        .split('/')
+        .map(|part| {
+            let part = part.trim_start_matches('.');
+            let part = part.strip_suffix(".lock").unwrap_or(part);
+            part.trim_end_matches('.').to_string()
+        })
+        .filter(|part| !part.is_empty())
+        .collect();
+    let branch = components.join("/");
+    if branch == "@" { String::new() } else { branch }
+}
+
 /// Information about a created worktree.
 pub struct WorktreeInfo {
     /// Filesystem path to the created worktree directory.
@@ -12,62 +89,185 @@ pub struct WorktreeInfo {
     pub branch: String,
     /// Branch that the new worktree branch was based on.
     pub base_branch: String,
+    /// Non-fatal problems hit while seeding the worktree.
+    pub warnings: Vec<String>,
 }
 
 /// Create a git worktree for an isolated bot run.
 ///
-/// The worktree is placed under `<repo>/.git/openbot-worktrees/<bot>-<ts>/`
-/// on a new branch `openbot/<bot>-<ts>`.
-pub fn create_worktree(repo_root: &Path, bot_name: &str) -> Result<WorktreeInfo> {
-    let base_branch = std::process::Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(repo_root)
-        .output()
-        .with_context(|| "running git rev-parse")?;
-    let base_branch = String::from_utf8_lossy(&base_branch.stdout)
-        .trim()
-        .to_string();
+/// The worktree is created on the new branch `branch` (see [`branch_name`])
+/// under `<repo>/.git/openbot-worktrees/`, in a directory named after the
+/// branch without its `openbot/` prefix and with `/` turned into `-`.
+///
+/// When `copy_dirty` is false the worktree starts from pristine HEAD instead
+/// of mirroring the user's uncommitted changes.
+///
+/// `init_submodules` controls whether submodules are initialized in the new
+/// worktree; `None` means "only when the repo has a `.gitmodules` file".
+pub fn create_worktree(
+    repo_root: &Path,
+    branch: &str,
+    copy_dirty: bool,
+    init_submodules: Option<bool>,
+) -> Result<WorktreeInfo> {
+    let base_branch = current_branch(repo_root).unwrap_or_default();
 
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    let suffix = format!("{bot_name}-{ts}");
-    let branch = format!("openbot/{suffix}");
-    let wt_path = repo_root.join(".git/openbot-worktrees").join(&suffix);
+    // Runs started in the same second render the same name; git refuses the
+    // second one, which then retries under a process-unique suffix.
+    let mut candidate = branch.to_string();
+    let mut attempt = 1;
+    let (branch, wt_path) = loop {
+        let dir_name = candidate
+            .strip_prefix(BRANCH_PREFIX)
+            .unwrap_or(&candidate)
+            .replace('/', "-");
+        let wt_path = repo_root.join(WORKTREE_DIR).join(&dir_name);
 
-    let output = std::process::Command::new("git")
-        .args(["worktree", "add", &wt_path.to_string_lossy(), "-b", &branch])
-        .current_dir(repo_root)
-        .output()
-        .with_context(|| "running git worktree add")?;
-    if !output.status.success() {
+        let output = std::process::Command::new("git")
+            .args([
+                "worktree",
+                "add",
+                &wt_path.to_string_lossy(),
+                "-b",
+                &candidate,
+            ])
+            .current_dir(repo_root)
+            .output()
+            .with_context(|| "running git worktree add")?;
+        if output.status.success() {
+            break (candidate, wt_path);
+        }
         let stderr = String::from_utf8_lossy(&output.stderr);
+        if attempt < WORKTREE_ADD_ATTEMPTS && stderr.contains("already exists") {
+            candidate = format!("{branch}-{}", unique_suffix());
+            attempt += 1;
+            continue;
+        }
         anyhow::bail!("git worktree add failed: {stderr}");
-    }
+    };
 
     // Copy uncommitted changes (tracked modifications + untracked files) into
     // the worktree so the bot sees the same state as the user's working tree.
-    copy_dirty_state(repo_root, &wt_path)?;
+    if copy_dirty {
+        copy_dirty_state(repo_root, &wt_path)?;
+    }
+
+    let mut warnings = Vec::new();
+    let init_submodules = init_submodules.unwrap_or_else(|| repo_root.join(".gitmodules").exists());
+    if init_submodules && let Err(err) = update_submodules(&wt_path) {
+        warnings.push(format!("submodule init failed: {err}"));
+    }
 
     Ok(WorktreeInfo {
         path: wt_path,
         branch,
         base_branch,
+        warnings,
     })
 }
 
+/// Process id plus a few clock bits, to tell concurrent runs apart.
+fn unique_suffix() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    format!("{}-{:04x}", std::process::id(), nanos & 0xffff)
+}
+
+/// The branch checked out in `repo`, or `"HEAD"` when detached; `None` if
+/// git fails.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(repo)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() { None } else { Some(name) }
+}
+
+/// Initialize and check out submodules in a worktree.
+///
+/// Only the committed submodule revisions are checked out; dirty state
+/// inside the user's submodules is not copied.
+fn update_submodules(wt_path: &Path) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .args(["submodule", "update", "--init", "--recursive"])
+        .current_dir(wt_path)
+        .output()
+        .with_context(|| "running git submodule update")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// A tracked change between HEAD and the working tree.
+#[derive(Debug, PartialEq, Eq)]
+enum TrackedChange {
+    /// Added, modified, type-changed, or copied — copy the path over.
+    Modified(String),
+    /// Deleted — remove the path from the worktree.
+    Deleted(String),
+    /// Renamed — remove `from` and copy `to`.
+    Renamed { from: String, to: String },
+}
+
+/// Parse `git diff --name-status -z` output into tracked changes.
+///
+/// Records are NUL-separated: `STATUS\0path\0`, or `R<score>\0old\0new\0`
+/// for renames and copies.
+fn parse_name_status(output: &[u8]) -> Vec<TrackedChange> {
+    let mut fields = output
+        .split(|&b| b == 0)
+        .map(|f| String::from_utf8_lossy(f).into_owned());
+    let mut changes = Vec::new();
+
+    while let Some(status) = fields.next() {
+        let Some(kind) = status.chars().next() else {
+            continue;
+        };
+        let Some(path) = fields.next() else {
+            break;
+        };
+        match kind {
+            'D' => changes.push(TrackedChange::Deleted(path)),
+            'R' => {
+                let Some(to) = fields.next() else {
+                    break;
+                };
+                changes.push(TrackedChange::Renamed { from: path, to });
+            }
+            'C' => {
+                let Some(to) = fields.next() else {
+                    break;
+                };
+                changes.push(TrackedChange::Modified(to));
+            }
+            _ => changes.push(TrackedChange::Modified(path)),
+        }
+    }
+
+    changes
+}
+
 /// Copy dirty working-tree state from the source repo into a fresh worktree.
 ///
 /// This handles two categories:
-/// 1. Tracked files with modifications (staged or unstaged) — copied via
-///    `git diff` to find changed paths, then file-level copy.
+/// 1. Tracked changes (staged or unstaged) vs HEAD — discovered via
+///    `git diff HEAD --name-status -z` so deletions, renames, and
+///    modifications are each applied precisely.
 /// 2. Untracked files — discovered via `git ls-files --others --exclude-standard`,
 ///    then copied with directory structure preserved.
 fn copy_dirty_state(repo_root: &Path, wt_path: &Path) -> Result<()> {
-    // 1. Tracked modifications (unstaged + staged vs HEAD).
+    // 1. Tracked changes (unstaged + staged vs HEAD).
     let diff_output = std::process::Command::new("git")
-        .args(["diff", "HEAD", "--name-only"])
+        .args(["diff", "HEAD", "--name-status", "-z", "-M"])
         .current_dir(repo_root)
         .output()
         .with_context(|| "listing tracked changes")?;
@@ -75,29 +275,30 @@ fn copy_dirty_state(repo_root: &Path, wt_path: &Path) -> Result<()> {
         let stderr = String::from_utf8_lossy(&diff_output.stderr);
         anyhow::bail!("listing tracked changes failed: {stderr}");
     }
-    let tracked_files = String::from_utf8_lossy(&diff_output.stdout);
 
-    for relpath in tracked_files.lines() {
-        let relpath = relpath.trim();
-        if relpath.is_empty() {
-            continue;
-        }
-        let src = repo_root.join(relpath);
-        let dst = wt_path.join(relpath);
-        if src.is_file() {
-            if let Some(parent) = dst.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("creating directory {}", parent.display()))?;
+    for change in parse_name_status(&diff_output.stdout) {
+        match change {
+            TrackedChange::Modified(relpath) => {
+                let src = repo_root.join(&relpath);
+                match std::fs::symlink_metadata(&src) {
+                    Ok(meta) if meta.is_file() || meta.is_symlink() => {
+                        copy_entry(&src, &wt_path.join(&relpath))?;
+                    }
+                    // A dirty submodule shows up as a modified directory;
+                    // submodules are initialized separately.
+                    Ok(_) => {}
+                    Err(_) => remove_entry(&wt_path.join(&relpath))?,
+                }
             }
-            std::fs::copy(&src, &dst)
-                .with_context(|| format!("copying {} to {}", src.display(), dst.display()))?;
-        } else if !src.exists() {
-            // File was deleted in the working tree — remove from worktree too.
-            if let Err(err) = std::fs::remove_file(&dst)
-                && err.kind() != std::io::ErrorKind::NotFound
-            {
-                return Err(err)
-                    .with_context(|| format!("removing deleted file {}", dst.display()));
+            TrackedChange::Deleted(relpath) => {
+                remove_entry(&wt_path.join(&relpath))?;
+            }
+            TrackedChange::Renamed { from, to } => {
+                remove_entry(&wt_path.join(&from))?;
+                let src = repo_root.join(&to);
+                if std::fs::symlink_metadata(&src).is_ok_and(|m| m.is_file() || m.is_symlink()) {
+                    copy_entry(&src, &wt_path.join(&to))?;
+                }
             }
         }
     }
@@ -121,16 +322,60 @@ fn copy_dirty_state(repo_root: &Path, wt_path: &Path) -> Result<()> {
         }
         let src = repo_root.join(relpath);
         let dst = wt_path.join(relpath);
-        if src.is_file() {
-            if let Some(parent) = dst.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("creating directory {}", parent.display()))?;
+        if std::fs::symlink_metadata(&src).is_ok_and(|m| m.is_file() || m.is_symlink()) {
+            copy_entry(&src, &dst)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a file or symlink from the worktree, ignoring paths already gone.
+fn remove_entry(dst: &Path) -> Result<()> {
+    match std::fs::remove_file(dst) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("removing deleted file {}", dst.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Copy one file or symlink into the worktree.
+///
+/// Symlinks are recreated as links (not followed) and regular files keep
+/// their permission bits, so executable scripts stay executable.
+fn copy_entry(src: &Path, dst: &Path) -> Result<()> {
+    let meta =
+        std::fs::symlink_metadata(src).with_context(|| format!("reading {}", src.display()))?;
+
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating directory {}", parent.display()))?;
+    }
+
+    // Never write through an existing link in the worktree.
+    if std::fs::symlink_metadata(dst).is_ok_and(|m| m.is_symlink()) {
+        std::fs::remove_file(dst).with_context(|| format!("removing {}", dst.display()))?;
+    }
+
+    if meta.is_symlink() {
+        #[cfg(unix)]
+        {
+            let target = std::fs::read_link(src)
+                .with_context(|| format!("reading link {}", src.display()))?;
+            if dst.exists() {
+                std::fs::remove_file(dst).with_context(|| format!("removing {}", dst.display()))?;
             }
-            std::fs::copy(&src, &dst)
-                .with_context(|| format!("copying {} to {}", src.display(), dst.display()))?;
+            std::os::unix::fs::symlink(&target, dst)
+                .with_context(|| format!("linking {} -> {}", dst.display(), target.display()))?;
+            return Ok(());
         }
     }
 
+    std::fs::copy(src, dst)
+        .with_context(|| format!("copying {} to {}", src.display(), dst.display()))?;
+    std::fs::set_permissions(dst, meta.permissions())
+        .with_context(|| format!("setting permissions on {}", dst.display()))?;
     Ok(())
 }
 
@@ -138,8 +383,36 @@ fn copy_dirty_state(repo_root: &Path, wt_path: &Path) -> Result<()> {
 ///
 /// The branch is intentionally kept so uncommitted work isn't lost.
 pub fn remove_worktree(path: &Path) -> Result<()> {
+    remove_worktree_with(path, &["--force"])
+}
+
+/// Lock a worktree so it outlives the run, marked for `openbot worktrees clean`.
+pub fn keep_worktree(path: &Path) -> Result<()> {
     let output = std::process::Command::new("git")
-        .args(["worktree", "remove", "--force", &path.to_string_lossy()])
+        .args(["worktree", "lock", "--reason", KEEP_REASON])
+        .arg(path)
+        .current_dir(path)
+        .output()
+        .with_context(|| "running git worktree lock")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git worktree lock failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// Remove a worktree kept with [`keep_worktree`], lock included.
+pub fn remove_kept_worktree(path: &Path) -> Result<()> {
+    // A second --force removes locked worktrees.
+    remove_worktree_with(path, &["--force", "--force"])
+}
+
+/// Run `git worktree remove` on `path` with the given force flags.
+fn remove_worktree_with(path: &Path, force: &[&str]) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .args(["worktree", "remove"])
+        .args(force)
+        .arg(path)
         .output()
         .with_context(|| "running git worktree remove")?;
     if !output.status.success() {
@@ -149,6 +422,229 @@ pub fn remove_worktree(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Stage and commit every change in `dir`, returning `false` if the tree was clean.
+pub fn commit_all(dir: &Path, message: &str) -> Result<bool> {
+    let status = std::process::Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(dir)
+        .output()
+        .with_context(|| "running git status")?;
+    if !status.status.success() {
+        let stderr = String::from_utf8_lossy(&status.stderr);
+        anyhow::bail!("git status failed: {}", stderr.trim());
+    }
+    if status.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(false);
+    }
+
+    for args in [&["add", "-A"][..], &["commit", "-q", "-m", message][..]] {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .with_context(|| format!("running git {}", args[0]))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git {} failed: {}", args[0], stderr.trim());
+        }
+    }
+    Ok(true)
+}
+
+/// Number of commits on `branch` that `base` doesn't have (`git rev-list --count base..branch`).
+pub fn commits_ahead(repo: &Path, base: &str, branch: &str) -> Result<usize> {
+    let output = std::process::Command::new("git")
+        .args(["rev-list", "--count", &format!("{base}..{branch}")])
+        .current_dir(repo)
+        .output()
+        .with_context(|| "running git rev-list")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git rev-list failed: {}", stderr.trim());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .with_context(|| "parsing git rev-list --count output")
+}
+
+/// List tracked and untracked-but-not-ignored files under `dir`, relative to it.
+pub fn list_files(dir: &Path) -> Result<Vec<String>> {
+    let output = std::process::Command::new("git")
+        .args([
+            "ls-files",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+            "-z",
+        ])
+        .current_dir(dir)
+        .output()
+        .with_context(|| "running git ls-files")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git ls-files failed: {}", stderr.trim());
+    }
+    let mut files: Vec<String> = output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|f| !f.is_empty())
+        .map(|f| String::from_utf8_lossy(f).into_owned())
+        .collect();
+    // Deleted-but-tracked files show up in --cached; keep only what's on disk.
+    files.retain(|f| dir.join(f).exists());
+    files.dedup();
+    Ok(files)
+}
+
+/// A registered git worktree that belongs to an openbot run.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OpenbotWorktree {
+    /// Worktree directory as recorded by git.
+    pub path: PathBuf,
+    /// Checked-out branch, without the `refs/heads/` prefix.
+    pub branch: Option<String>,
+    /// True when git reports the worktree directory as missing.
+    pub prunable: bool,
+    /// True when the run kept it with `--keep-worktree` (see [`keep_worktree`]).
+    pub kept: bool,
+}
+
+/// List worktrees created by openbot, from `git worktree list --porcelain`.
+pub fn list_openbot_worktrees(repo_root: &Path) -> Result<Vec<OpenbotWorktree>> {
+    let output = std::process::Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .current_dir(repo_root)
+        .output()
+        .with_context(|| "running git worktree list")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git worktree list failed: {}", stderr.trim());
+    }
+    Ok(parse_worktree_list(
+        &String::from_utf8_lossy(&output.stdout),
+        &repo_root.join(WORKTREE_DIR),
+    ))
+}
+
+/// Parse porcelain worktree records, keeping those on an `openbot/` branch
+/// or located under `worktree_root`.
+fn parse_worktree_list(output: &str, worktree_root: &Path) -> Vec<OpenbotWorktree> {
+    let mut worktrees = Vec::new();
+    for record in output.split("\n\n") {
+        let mut path = None;
+        let mut branch = None;
+        let mut prunable = false;
+        let mut kept = false;
+        for line in record.lines() {
+            if let Some(p) = line.strip_prefix("worktree ") {
+                path = Some(PathBuf::from(p));
+            } else if let Some(b) = line.strip_prefix("branch ") {
+                branch = Some(b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
+            } else if line == "prunable" || line.starts_with("prunable ") {
+                prunable = true;
+            } else if line.strip_prefix("locked ") == Some(KEEP_REASON) {
+                kept = true;
+            }
+        }
+        let Some(path) = path else { continue };
+        let ours = path.starts_with(worktree_root)
+            || branch
+                .as_deref()
+                .is_some_and(|b| b.starts_with(BRANCH_PREFIX));
+        if ours {
+            worktrees.push(OpenbotWorktree {
+                path,
+                branch,
+                prunable,
+                kept,
+            });
+        }
+    }
+    worktrees
+}
+
+/// List `openbot/` branches, optionally only those merged into HEAD.
+pub fn list_openbot_branches(repo_root: &Path, merged_only: bool) -> Result<Vec<String>> {
+    let pattern = format!("refs/heads/{BRANCH_PREFIX}");
+    let mut args = vec!["for-each-ref", "--format=%(refname:short)"];
+    if merged_only {
+        args.push("--merged=HEAD");
+    }
+    args.push(&pattern);
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(repo_root)
+        .output()
+        .with_context(|| "running git for-each-ref")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git for-each-ref failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Directories under the openbot worktree root that git no longer tracks.
+pub fn orphaned_worktree_dirs(
+    repo_root: &Path,
+    registered: &[OpenbotWorktree],
+) -> Result<Vec<PathBuf>> {
+    let root = repo_root.join(WORKTREE_DIR);
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    // git may report canonicalized paths, so compare canonical forms.
+    let known: Vec<PathBuf> = registered
+        .iter()
+        .map(|wt| wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone()))
+        .collect();
+
+    let mut orphans = Vec::new();
+    for entry in std::fs::read_dir(&root).with_context(|| format!("reading {}", root.display()))? {
+        let path = entry?.path();
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if path.is_dir() && !known.contains(&canonical) {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Drop git's records of worktrees whose directories no longer exist.
+pub fn prune_worktrees(repo_root: &Path) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .args(["worktree", "prune"])
+        .current_dir(repo_root)
+        .output()
+        .with_context(|| "running git worktree prune")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git worktree prune failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// Force-delete a local branch.
+pub fn delete_branch(repo_root: &Path, branch: &str) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .args(["branch", "-D", branch])
+        .current_dir(repo_root)
+        .output()
+        .with_context(|| "running git branch -D")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git branch -D {branch} failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
 /// Resolve the root git project for a directory, handling worktrees correctly.
 ///
 /// Uses `git rev-parse --git-common-dir` so that worktrees of the same repo
@@ -188,3 +684,323 @@ impl Drop for WorktreeGuard {
         remove_worktree(&self.path).ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+    use std::fs;
+
+    /// Run git in `dir`, panicking on failure.
+    fn git(dir: &Path, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("run git");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Initialize a repo with one committed README.
+    fn init_repo(dir: &Path) {
+        git(dir, &["init"]);
+        git(dir, &["config", "user.email", "openbot-test@example.com"]);
+        git(dir, &["config", "user.name", "openbot-test"]);
+        fs::write(dir.join("README.md"), "base\n").expect("write readme");
+        git(dir, &["add", "README.md"]);
+        git(dir, &["commit", "-m", "base"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dirty_state_keeps_modes_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = TempDir::new("git-repo");
+        let wt = TempDir::new("git-wt");
+        init_repo(&repo);
+
+        let script = repo.join("run.sh");
+        fs::write(&script, "#!/bin/sh\necho hi\n").expect("write script");
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+        std::os::unix::fs::symlink("README.md", repo.join("link.md")).expect("symlink");
+
+        copy_dirty_state(&repo, &wt).expect("copy dirty state");
+
+        let mode = fs::metadata(wt.join("run.sh"))
+            .expect("script meta")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+        let link = wt.join("link.md");
+        assert!(fs::symlink_metadata(&link).expect("link meta").is_symlink());
+        assert_eq!(
+            fs::read_link(&link).expect("read link"),
+            PathBuf::from("README.md")
+        );
+    }
+
+    #[test]
+    fn parse_name_status_handles_renames_and_deletes() {
+        let out = b"M\0src/lib.rs\0D\0old.txt\0R087\0a.rs\0b.rs\0C100\0x.rs\0y.rs\0";
+        assert_eq!(
+            parse_name_status(out),
+            vec![
+                TrackedChange::Modified("src/lib.rs".into()),
+                TrackedChange::Deleted("old.txt".into()),
+                TrackedChange::Renamed {
+                    from: "a.rs".into(),
+                    to: "b.rs".into(),
+                },
+                TrackedChange::Modified("y.rs".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_dirty_state_applies_staged_rename_and_delete() {
+        let repo = TempDir::new("git-rename-repo");
+        init_repo(&repo);
+        fs::write(repo.join("gone.txt"), "bye\n").expect("write gone");
+        git(&repo, &["add", "gone.txt"]);
+        git(&repo, &["commit", "-m", "add gone"]);
+
+        let wt = repo.join(".git/openbot-worktrees/test");
+        git(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                &wt.to_string_lossy(),
+                "-b",
+                "openbot/test",
+            ],
+        );
+
+        git(&repo, &["mv", "README.md", "DOCS.md"]);
+        git(&repo, &["rm", "-q", "gone.txt"]);
+
+        copy_dirty_state(&repo, &wt).expect("copy dirty state");
+
+        assert!(
+            !wt.join("README.md").exists(),
+            "renamed source should be gone"
+        );
+        assert_eq!(
+            fs::read_to_string(wt.join("DOCS.md")).expect("read renamed"),
+            "base\n"
+        );
+        assert!(!wt.join("gone.txt").exists(), "deleted file should be gone");
+    }
+
+    #[test]
+    fn copy_dirty_state_skips_dirty_submodules() {
+        let root = TempDir::new("git-submodule");
+        let (upstream, repo) = (root.join("upstream"), root.join("repo"));
+        fs::create_dir_all(&upstream).expect("create upstream");
+        fs::create_dir_all(&repo).expect("create repo");
+        init_repo(&upstream);
+        init_repo(&repo);
+        git(
+            &repo,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                &upstream.to_string_lossy(),
+                "sub",
+            ],
+        );
+        git(&repo, &["commit", "-m", "add submodule"]);
+
+        let wt = repo.join(".git/openbot-worktrees/test");
+        git(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                &wt.to_string_lossy(),
+                "-b",
+                "openbot/test",
+            ],
+        );
+
+        // Untracked content and a new commit both mark the submodule modified.
+        fs::write(repo.join("sub/scratch.txt"), "wip\n").expect("write in submodule");
+        fs::write(repo.join("sub/README.md"), "moved on\n").expect("edit submodule");
+        git(
+            &repo.join("sub"),
+            &[
+                "-c",
+                "user.name=openbot-test",
+                "-c",
+                "user.email=openbot-test@example.com",
+                "commit",
+                "-qam",
+                "advance",
+            ],
+        );
+        fs::write(repo.join("README.md"), "dirty\n").expect("write readme");
+
+        copy_dirty_state(&repo, &wt).expect("copy dirty state");
+
+        assert_eq!(
+            fs::read_to_string(wt.join("README.md")).expect("read readme"),
+            "dirty\n"
+        );
+        assert!(!wt.join("sub/scratch.txt").exists());
+    }
+
+    #[test]
+    fn commit_all_commits_only_dirty_trees() {
+        let repo = TempDir::new("git-commit-all");
+        init_repo(&repo);
+
+        assert!(!commit_all(&repo, "noop").expect("clean commit_all"));
+
+        fs::write(repo.join("new.txt"), "new\n").expect("write new");
+        fs::write(repo.join("README.md"), "changed\n").expect("write readme");
+        assert!(commit_all(&repo, "bot work").expect("dirty commit_all"));
+
+        let log = std::process::Command::new("git")
+            .args(["log", "-1", "--format=%s"])
+            .current_dir(&repo)
+            .output()
+            .expect("git log");
+        assert_eq!(String::from_utf8_lossy(&log.stdout).trim(), "bot work");
+        assert!(!commit_all(&repo, "noop").expect("clean after commit"));
+    }
+
+    #[test]
+    fn parse_worktree_list_keeps_openbot_entries() {
+        let root = Path::new("/repo/.git/openbot-worktrees");
+        let out = "worktree /repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                   worktree /repo/.git/openbot-worktrees/bot-1\nHEAD def\nbranch refs/heads/openbot/bot-1\n\n\
+                   worktree /repo/.git/openbot-worktrees/bot-3\nHEAD fed\nbranch refs/heads/openbot/bot-3\nlocked kept by openbot run --keep-worktree\n\n\
+                   worktree /elsewhere/gone\nHEAD 123\nbranch refs/heads/openbot/bot-2\nprunable gitdir file points to non-existent location\n\n\
+                   worktree /tmp/feature\nHEAD 456\nbranch refs/heads/feature\n";
+        assert_eq!(
+            parse_worktree_list(out, root),
+            vec![
+                OpenbotWorktree {
+                    path: PathBuf::from("/repo/.git/openbot-worktrees/bot-1"),
+                    branch: Some("openbot/bot-1".into()),
+                    prunable: false,
+                    kept: false,
+                },
+                OpenbotWorktree {
+                    path: PathBuf::from("/repo/.git/openbot-worktrees/bot-3"),
+                    branch: Some("openbot/bot-3".into()),
+                    prunable: false,
+                    kept: true,
+                },
+                OpenbotWorktree {
+                    path: PathBuf::from("/elsewhere/gone"),
+                    branch: Some("openbot/bot-2".into()),
+                    prunable: true,
+                    kept: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn orphaned_dirs_and_merged_branches() {
+        let repo = TempDir::new("git-clean");
+        init_repo(&repo);
+
+        let branch = branch_name(
+            DEFAULT_BRANCH_TEMPLATE,
+            &BranchVars {
+                bot: "bot",
+                slug: "repo",
+                session: 1,
+            },
+        );
+        let info = create_worktree(&repo, &branch, false, Some(false)).expect("create worktree");
+        git(&repo, &["branch", "openbot/old-1"]);
+        let orphan = repo.join(WORKTREE_DIR).join("old-1");
+        fs::create_dir_all(&orphan).expect("create orphan dir");
+
+        let registered = list_openbot_worktrees(&repo).expect("list worktrees");
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].branch.as_deref(), Some(info.branch.as_str()));
+        assert_eq!(
+            orphaned_worktree_dirs(&repo, &registered).expect("orphans"),
+            vec![orphan]
+        );
+
+        fs::write(info.path.join("bot.txt"), "bot\n").expect("write bot file");
+        assert!(commit_all(&info.path, "bot work").expect("commit bot work"));
+        assert_eq!(
+            commits_ahead(&repo, &info.base_branch, &info.branch).expect("count"),
+            1
+        );
+        assert_eq!(
+            list_openbot_branches(&repo, true).expect("merged branches"),
+            vec!["openbot/old-1".to_string()]
+        );
+        assert_eq!(list_openbot_branches(&repo, false).expect("all").len(), 2);
+    }
+
+    #[test]
+    fn branch_name_fills_placeholders_and_sanitizes() {
+        let vars = BranchVars {
+            bot: "reviewer",
+            slug: "my-app",
+            session: 7,
+        };
+        let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            branch_name("feature/{bot}-{date}", &vars),
+            format!("feature/reviewer-{date}")
+        );
+        assert_eq!(
+            branch_name("{slug}/{bot}/s{n}", &vars),
+            "my-app/reviewer/s7"
+        );
+        assert!(branch_name(DEFAULT_BRANCH_TEMPLATE, &vars).starts_with("openbot/reviewer-"));
+
+        assert_eq!(
+            branch_name("bots/{bot} run: #{n}?", &vars),
+            "bots/reviewer-run--#7-"
+        );
+        assert_eq!(branch_name("a//.hidden/x.lock/..y.", &vars), "a/hidden/x/y");
+        assert_eq!(branch_name("a...b/c....d", &vars), "a.b/c.d");
+        assert!(branch_name("///", &vars).starts_with("openbot/reviewer-"));
+    }
+
+    #[test]
+    fn create_worktree_retries_when_branch_or_path_collides() {
+        let repo = TempDir::new("git-collide");
+        init_repo(&repo);
+
+        let first = create_worktree(&repo, "openbot/bot-1", false, Some(false)).expect("first");
+        // Same name again: the branch exists and the directory is taken.
+        let second = create_worktree(&repo, "openbot/bot-1", false, Some(false)).expect("second");
+        assert_ne!(second.branch, first.branch);
+        assert!(
+            second.branch.starts_with("openbot/bot-1-"),
+            "{}",
+            second.branch
+        );
+        assert_ne!(second.path, first.path);
+
+        // A leftover non-empty directory with no branch also collides.
+        let stale = repo.join(WORKTREE_DIR).join("bot-2");
+        fs::create_dir_all(&stale).expect("create stale dir");
+        fs::write(stale.join("leftover"), "x").expect("write leftover");
+        let third = create_worktree(&repo, "openbot/bot-2", false, Some(false)).expect("third");
+        assert_ne!(third.path, stale);
+        assert!(
+            third.branch.starts_with("openbot/bot-2-"),
+            "{}",
+            third.branch
+        );
+    }
+}
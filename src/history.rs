@@ -7,13 +7,23 @@
 //!
 //! Legacy `history/{session_id}.json` files are still readable for backward
 //! compatibility.
+//!
+//! A `sessions.json` index next to `history/` maps session numbers to IDs so
+//! lookups don't scan every directory. It is rebuilt from `history/` when
+//! missing or unreadable.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, File};
 use std::io::{BufRead, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::warn;
+
+use crate::util::{atomic_write, truncate_with_ellipsis};
 
 /// A command executed during a session.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +67,9 @@ pub struct SessionRecord {
     /// Number of commands executed (for quick display without reading events).
     #[serde(default)]
     pub command_count: Option<usize>,
+    /// Estimated cost in USD, when `[pricing]` covers the model.
+    #[serde(default)]
+    pub est_cost_usd: Option<f64>,
 }
 
 /// An event captured during a session, streamed to `events.jsonl`.
@@ -78,12 +91,38 @@ pub enum SessionEvent {
         reasoning_output_tokens: i64,
         context_window: Option<i64>,
     },
+    /// Model reasoning, recorded only when `show_reasoning` is on.
+    Reasoning {
+        content: String,
+    },
+    /// The agent called `session_complete`.
+    SessionComplete {
+        summary: String,
+        action: String,
+    },
 }
 
+/// Streamed text events (messages, reasoning) are flushed at least this often...
+pub const STREAM_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ...or after this many unflushed writes, whichever comes first.
+const STREAM_FLUSH_EVERY: usize = 64;
+
+/// Longest run of message text held back waiting for a newline.
+const MESSAGE_CHUNK_MAX: usize = 4096;
+
 /// Streams session events to disk as they happen.
+///
+/// Message deltas are coalesced into one `Message` event per line (or
+/// [`MESSAGE_CHUNK_MAX`] bytes), written early when any other event arrives.
+/// Commands, token counts, and completion are flushed immediately; streamed
+/// text is batched so a chatty session doesn't cost a write syscall per chunk.
 pub struct SessionWriter {
     session_dir: PathBuf,
     writer: BufWriter<File>,
+    pending_message: String,
+    unflushed: usize,
+    last_flush: Instant,
 }
 
 impl SessionWriter {
@@ -97,7 +136,7 @@ impl SessionWriter {
         let meta_path = session_dir.join("metadata.json");
         let json =
             serde_json::to_string_pretty(record).with_context(|| "serializing initial metadata")?;
-        fs::write(&meta_path, json).with_context(|| "writing initial metadata")?;
+        atomic_write(&meta_path, json).with_context(|| "writing initial metadata")?;
 
         // Open events file for appending.
         let events_path = session_dir.join("events.jsonl");
@@ -108,28 +147,191 @@ impl SessionWriter {
         Ok(Self {
             session_dir,
             writer,
+            pending_message: String::new(),
+            unflushed: 0,
+            last_flush: Instant::now(),
         })
     }
 
     /// Append a single event to the events.jsonl file.
     pub fn append_event(&mut self, event: &SessionEvent) -> Result<()> {
+        let streamed = match event {
+            SessionEvent::Message { content } => {
+                self.pending_message.push_str(content);
+                if self.pending_message.len() >= MESSAGE_CHUNK_MAX {
+                    self.write_pending_message()?;
+                } else if content.contains('\n') {
+                    // Write through the last complete line; keep the rest pending.
+                    let end = self.pending_message.rfind('\n').map_or(0, |i| i + 1);
+                    let rest = self.pending_message.split_off(end);
+                    self.write_pending_message()?;
+                    self.pending_message = rest;
+                }
+                true
+            }
+            _ => {
+                self.write_pending_message()?;
+                self.write_line(event)?;
+                matches!(event, SessionEvent::Reasoning { .. })
+            }
+        };
+
+        if !streamed
+            || self.unflushed >= STREAM_FLUSH_EVERY
+            || self.last_flush.elapsed() >= STREAM_FLUSH_INTERVAL
+        {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, event: &SessionEvent) -> Result<()> {
         let line = serde_json::to_string(event).with_context(|| "serializing event")?;
         writeln!(self.writer, "{line}").with_context(|| "writing event")?;
+        self.unflushed += 1;
+        Ok(())
+    }
+
+    fn write_pending_message(&mut self) -> Result<()> {
+        if self.pending_message.is_empty() {
+            return Ok(());
+        }
+        let content = std::mem::take(&mut self.pending_message);
+        self.write_line(&SessionEvent::Message { content })
+    }
+
+    /// Write any buffered events to disk.
+    pub fn flush(&mut self) -> Result<()> {
+        self.write_pending_message()?;
         self.writer.flush().with_context(|| "flushing events")?;
+        self.unflushed = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Flush if anything has waited [`STREAM_FLUSH_INTERVAL`] or longer.
+    ///
+    /// `append_event` only checks the interval when the next event arrives;
+    /// the runner calls this on a timer so a stream that goes quiet still
+    /// reaches disk.
+    pub fn flush_if_due(&mut self) -> Result<()> {
+        let buffered = self.unflushed > 0 || !self.pending_message.is_empty();
+        if buffered && self.last_flush.elapsed() >= STREAM_FLUSH_INTERVAL {
+            self.flush()?;
+        }
         Ok(())
     }
 
-    /// Overwrite metadata.json with final values and drop the file handle.
-    pub fn finalize(self, record: &SessionRecord) -> Result<()> {
+    /// Flush remaining events, overwrite metadata.json with final values,
+    /// update the session index, and drop the file handle.
+    pub fn finalize(mut self, record: &SessionRecord) -> Result<()> {
+        self.flush()?;
         let meta_path = self.session_dir.join("metadata.json");
         let json =
             serde_json::to_string_pretty(record).with_context(|| "serializing final metadata")?;
-        fs::write(&meta_path, json).with_context(|| "writing final metadata")?;
+        atomic_write(&meta_path, json).with_context(|| "writing final metadata")?;
+        if let Some(history_dir) = self.session_dir.parent() {
+            update_index(history_dir, record)?;
+        }
         // writer is dropped here, closing events.jsonl
         Ok(())
     }
 }
 
+/// One row of the per-workspace `sessions.json` index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionIndexEntry {
+    pub session_number: usize,
+    /// History directory name (`{codex_session_id}-s{session_number}`).
+    pub session_id: String,
+    /// Codex session ID to pass to `--resume`.
+    pub codex_session_id: String,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+impl SessionIndexEntry {
+    fn from_record(record: &SessionRecord) -> Self {
+        // History IDs append `-s{n}` to the codex ID; legacy records use it bare.
+        let suffix = format!("-s{}", record.session_number);
+        let codex_session_id = record
+            .session_id
+            .strip_suffix(&suffix)
+            .unwrap_or(&record.session_id)
+            .to_string();
+        Self {
+            session_number: record.session_number,
+            session_id: record.session_id.clone(),
+            codex_session_id,
+            started_at: record.started_at,
+            action: record.action.clone(),
+        }
+    }
+}
+
+/// On-disk shape of `sessions.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionIndex {
+    sessions: Vec<SessionIndexEntry>,
+}
+
+/// Path of the session index for a workspace's `history/` directory.
+fn index_path(history_dir: &Path) -> PathBuf {
+    history_dir.with_file_name("sessions.json")
+}
+
+fn write_index(history_dir: &Path, entries: &[SessionIndexEntry]) -> Result<()> {
+    let index = SessionIndex {
+        sessions: entries.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&index).with_context(|| "serializing session index")?;
+    atomic_write(&index_path(history_dir), json).with_context(|| "writing session index")
+}
+
+/// Load the session index, rebuilding it from `history/` if it is missing or unreadable.
+pub fn index(history_dir: &Path) -> Result<Vec<SessionIndexEntry>> {
+    let path = index_path(history_dir);
+    if let Ok(contents) = fs::read_to_string(&path)
+        && let Ok(index) = serde_json::from_str::<SessionIndex>(&contents)
+    {
+        return Ok(index.sessions);
+    }
+    let entries: Vec<SessionIndexEntry> = list(history_dir)?
+        .iter()
+        .map(SessionIndexEntry::from_record)
+        .collect();
+    if history_dir.exists() {
+        write_index(history_dir, &entries)?;
+    }
+    Ok(entries)
+}
+
+/// Insert or replace `record`'s row in the session index.
+fn update_index(history_dir: &Path, record: &SessionRecord) -> Result<()> {
+    let mut entries = index(history_dir)?;
+    entries.retain(|e| e.session_id != record.session_id);
+    entries.push(SessionIndexEntry::from_record(record));
+    entries.sort_by_key(|e| e.session_number);
+    write_index(history_dir, &entries)
+}
+
+/// Look up a session by number in the index.
+pub fn find_by_number(
+    history_dir: &Path,
+    session_number: usize,
+) -> Result<Option<SessionIndexEntry>> {
+    Ok(index(history_dir)?
+        .into_iter()
+        .rev()
+        .find(|e| e.session_number == session_number))
+}
+
+/// Parse a `#N` session reference into `N`.
+pub fn parse_session_ref(reference: &str) -> Option<usize> {
+    reference.strip_prefix('#')?.parse().ok()
+}
+
 /// Load a single session record by ID (directory format first, then legacy .json).
 pub fn load(history_dir: &Path, session_id: &str) -> Result<SessionRecord> {
     // Try new directory format first.
@@ -161,18 +363,14 @@ pub fn list(history_dir: &Path) -> Result<Vec<SessionRecord>> {
         if path.is_dir() {
             // New directory format: read metadata.json
             let meta_path = path.join("metadata.json");
-            if meta_path.exists() {
-                let contents = fs::read_to_string(&meta_path)
-                    .with_context(|| format!("reading {}", meta_path.display()))?;
-                if let Ok(record) = serde_json::from_str::<SessionRecord>(&contents) {
-                    records.push(record);
-                }
+            if meta_path.exists()
+                && let Some(record) = read_record_cached(&meta_path)?
+            {
+                records.push(record);
             }
         } else if path.extension().is_some_and(|ext| ext == "json") {
             // Legacy single-file format.
-            let contents =
-                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
-            if let Ok(record) = serde_json::from_str::<SessionRecord>(&contents) {
+            if let Some(record) = read_record_cached(&path)? {
                 records.push(record);
             }
         }
@@ -181,6 +379,62 @@ pub fn list(history_dir: &Path) -> Result<Vec<SessionRecord>> {
     Ok(records)
 }
 
+/// A parsed metadata file and the file state it was parsed from.
+struct CachedRecord {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// `None` when the file didn't parse as a record.
+    record: Option<SessionRecord>,
+}
+
+/// Parsed metadata by path, so listing history each iteration only stats
+/// files instead of re-reading and re-parsing every session.
+static RECORD_CACHE: LazyLock<Mutex<HashMap<PathBuf, CachedRecord>>> =
+    LazyLock::new(Default::default);
+
+/// Read a session record, reusing the cached parse while the file's mtime
+/// and size are unchanged.
+fn read_record_cached(path: &Path) -> Result<Option<SessionRecord>> {
+    let meta = fs::metadata(path).with_context(|| format!("reading {}", path.display()))?;
+    let (modified, len) = (meta.modified().ok(), meta.len());
+    let mut cache = RECORD_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache.get(path)
+        && cached.modified.is_some()
+        && cached.modified == modified
+        && cached.len == len
+    {
+        return Ok(cached.record.clone());
+    }
+
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let record = serde_json::from_str::<SessionRecord>(&contents).ok();
+    cache.insert(
+        path.to_path_buf(),
+        CachedRecord {
+            modified,
+            len,
+            record: record.clone(),
+        },
+    );
+    Ok(record)
+}
+
+/// Number for the next session: one past the highest number on disk or
+/// `previous`, whichever is larger.
+///
+/// Unlike `count() + 1`, this never reuses a number after a session was
+/// deleted. The number isn't reserved until the session's directory is
+/// written, so two runs in the same workspace that both ask before either
+/// writes can get the same number.
+pub fn next_session_number(history_dir: &Path, previous: usize) -> usize {
+    let last = list(history_dir)
+        .ok()
+        .and_then(|records| records.iter().map(|r| r.session_number).max())
+        .unwrap_or(0);
+    last.max(previous) + 1
+}
+
 /// Count session records without loading them all.
 pub fn count(history_dir: &Path) -> usize {
     if !history_dir.exists() {
@@ -211,25 +465,51 @@ pub fn recent(history_dir: &Path, n: usize) -> Result<Vec<SessionRecord>> {
     Ok(all[start..].to_vec())
 }
 
-/// Load all events from a session's events.jsonl file.
+/// Events read from `events.jsonl`, with a count of lines that didn't parse.
+#[derive(Debug, Default)]
+pub struct LoadedEvents {
+    pub events: Vec<SessionEvent>,
+    /// Non-empty lines that were not a valid event, e.g. a line cut off by a
+    /// crash mid-write.
+    pub malformed: usize,
+}
+
+/// Load all events from a session's events.jsonl file, skipping lines that
+/// don't parse.
 pub fn load_events(history_dir: &Path, session_id: &str) -> Result<Vec<SessionEvent>> {
+    let loaded = load_events_checked(history_dir, session_id)?;
+    if loaded.malformed > 0 {
+        warn!(
+            session = session_id,
+            malformed = loaded.malformed,
+            "skipped unparseable event lines"
+        );
+    }
+    Ok(loaded.events)
+}
+
+/// Like [`load_events`], but reports how many lines were skipped so the CLI
+/// can tell the user the stream is damaged.
+pub fn load_events_checked(history_dir: &Path, session_id: &str) -> Result<LoadedEvents> {
     let events_path = history_dir.join(session_id).join("events.jsonl");
     if !events_path.exists() {
-        return Ok(Vec::new());
+        return Ok(LoadedEvents::default());
     }
     let file =
         File::open(&events_path).with_context(|| format!("opening {}", events_path.display()))?;
     let reader = std::io::BufReader::new(file);
-    let mut events = Vec::new();
+    let mut loaded = LoadedEvents::default();
     for line in reader.lines() {
         let line = line.with_context(|| "reading event line")?;
-        if !line.trim().is_empty()
-            && let Ok(event) = serde_json::from_str::<SessionEvent>(&line)
-        {
-            events.push(event);
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<SessionEvent>(&line) {
+            Ok(event) => loaded.events.push(event),
+            Err(_) => loaded.malformed += 1,
         }
     }
-    Ok(events)
+    Ok(loaded)
 }
 
 /// Reconstruct the full agent response text by joining all Message events.
@@ -261,3 +541,565 @@ pub fn extract_commands(events: &[SessionEvent]) -> Vec<CommandEntry> {
         })
         .collect()
 }
+
+/// How the commands of one session differ from another's.
+#[derive(Debug, Default)]
+pub struct CommandDiff {
+    /// Commands only the second session ran.
+    pub added: Vec<CommandEntry>,
+    /// Commands only the first session ran.
+    pub removed: Vec<CommandEntry>,
+    /// Commands both ran with a different exit code, as `(before, after)`.
+    pub changed: Vec<(CommandEntry, CommandEntry)>,
+    /// Commands both ran with the same exit code.
+    pub unchanged: usize,
+}
+
+impl CommandDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two sessions' commands by command string.
+///
+/// A command that runs several times is matched occurrence by occurrence, so
+/// running `cargo test` twice where it used to run once shows up as one
+/// addition. Durations are ignored.
+pub fn diff_commands(before: &[CommandEntry], after: &[CommandEntry]) -> CommandDiff {
+    let mut pending: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (i, cmd) in before.iter().enumerate() {
+        pending
+            .entry(cmd.command.as_str())
+            .or_default()
+            .push_back(i);
+    }
+
+    let mut diff = CommandDiff::default();
+    for cmd in after {
+        match pending
+            .get_mut(cmd.command.as_str())
+            .and_then(VecDeque::pop_front)
+        {
+            Some(i) if before[i].exit_code != cmd.exit_code => {
+                diff.changed.push((before[i].clone(), cmd.clone()));
+            }
+            Some(_) => diff.unchanged += 1,
+            None => diff.added.push(cmd.clone()),
+        }
+    }
+
+    let mut unmatched: Vec<usize> = pending.into_values().flatten().collect();
+    unmatched.sort_unstable();
+    diff.removed = unmatched.into_iter().map(|i| before[i].clone()).collect();
+    diff
+}
+
+/// Render a session as a self-contained Markdown transcript: a metadata
+/// header, the commands run, the agent's summary, and the full response.
+pub fn to_markdown(record: &SessionRecord, events: &[SessionEvent]) -> String {
+    let mut out = format!("# Session #{}\n\n", record.session_number);
+    out.push_str(&format!("- **Session:** `{}`\n", record.session_id));
+    out.push_str(&format!(
+        "- **Date:** {}\n",
+        record.started_at.format("%Y-%m-%d %H:%M UTC")
+    ));
+    let secs = record.duration_secs;
+    let duration = if secs >= 60 {
+        format!("{}m{}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    };
+    out.push_str(&format!("- **Duration:** {duration}\n"));
+    out.push_str(&format!("- **Model:** {}\n", record.model));
+    if let Some(t) = &record.tokens {
+        out.push_str(&format!(
+            "- **Tokens:** {} input ({} cached) / {} output ({} reasoning)\n",
+            t.input_tokens, t.cached_input_tokens, t.output_tokens, t.reasoning_output_tokens
+        ));
+    }
+    out.push_str(&format!(
+        "- **Action:** {}\n",
+        record.action.as_deref().unwrap_or("-")
+    ));
+
+    if !record.prompt_summary.is_empty() {
+        out.push_str(&format!("\n## Prompt\n\n{}\n", record.prompt_summary));
+    }
+
+    let commands = extract_commands(events);
+    if !commands.is_empty() {
+        out.push_str(&format!("\n## Commands ({})\n\n", commands.len()));
+        for cmd in &commands {
+            let status = if cmd.exit_code == 0 {
+                "ok".to_string()
+            } else {
+                format!("exit {}", cmd.exit_code)
+            };
+            out.push_str(&format!(
+                "- {} — {status}, {}ms\n",
+                inline_code(&cmd.command),
+                cmd.duration_ms
+            ));
+        }
+    }
+
+    let summary = completion(events).map_or(record.response_summary.as_str(), |(s, _)| s);
+    if !summary.is_empty() {
+        out.push_str(&format!("\n## Summary\n\n{summary}\n"));
+    }
+
+    let response = reconstruct_response(events);
+    if !response.trim().is_empty() {
+        out.push_str(&format!("\n## Response\n\n{}\n", response.trim_end()));
+    }
+    out
+}
+
+/// Wrap `text` in a Markdown code span, using a longer backtick fence when
+/// the text itself contains backticks.
+fn inline_code(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    if longest == 0 {
+        format!("{fence}{text}{fence}")
+    } else {
+        format!("{fence} {text} {fence}")
+    }
+}
+
+/// The `(summary, action)` the agent passed to `session_complete`, if it did.
+pub fn completion(events: &[SessionEvent]) -> Option<(&str, &str)> {
+    events.iter().rev().find_map(|event| match event {
+        SessionEvent::SessionComplete { summary, action } => {
+            Some((summary.as_str(), action.as_str()))
+        }
+        _ => None,
+    })
+}
+
+/// A match found by [`search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub session_number: usize,
+    pub started_at: DateTime<Utc>,
+    /// Where the match is, e.g. `response line 12` or `command 3`.
+    pub location: String,
+    /// The matching line, trimmed and capped in length.
+    pub snippet: String,
+}
+
+/// Find lines in past responses and commands containing `query`
+/// (case-insensitive), newest session first, returning at most `max_hits`.
+pub fn search(history_dir: &Path, query: &str, max_hits: usize) -> Result<Vec<SearchHit>> {
+    const SNIPPET_BYTES: usize = 160;
+
+    let needle = query.to_lowercase();
+    let mut hits = Vec::new();
+    for record in list(history_dir)?.iter().rev() {
+        let events = load_events(history_dir, &record.session_id).unwrap_or_default();
+        let response = reconstruct_response(&events);
+        let responses = response
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (format!("response line {}", i + 1), line.to_string()));
+        let commands = extract_commands(&events)
+            .into_iter()
+            .enumerate()
+            .map(|(i, cmd)| (format!("command {}", i + 1), format!("$ {}", cmd.command)));
+
+        for (location, line) in responses.chain(commands) {
+            if !line.to_lowercase().contains(&needle) {
+                continue;
+            }
+            hits.push(SearchHit {
+                session_number: record.session_number,
+                started_at: record.started_at,
+                location,
+                snippet: truncate_with_ellipsis(line.trim(), SNIPPET_BYTES),
+            });
+            if hits.len() >= max_hits {
+                return Ok(hits);
+            }
+        }
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    fn record(session_id: &str, session_number: usize) -> SessionRecord {
+        SessionRecord {
+            session_id: session_id.into(),
+            session_number,
+            started_at: Utc::now(),
+            duration_secs: 1,
+            model: "test".into(),
+            prompt_summary: String::new(),
+            response_summary: String::new(),
+            action: None,
+            tokens: None,
+            command_count: None,
+            est_cost_usd: None,
+        }
+    }
+
+    #[test]
+    fn index_is_rebuilt_then_updated_on_finalize() {
+        let root = TempDir::new("history-index");
+        let dir = root.join("history");
+
+        let first = record("abc-s1", 1);
+        SessionWriter::create(&dir, &first)
+            .and_then(|w| w.finalize(&first))
+            .expect("write first");
+        // Drop the index so the next finalize has to rebuild it.
+        fs::remove_file(root.join("sessions.json")).expect("index written");
+
+        let mut second = record("abc-s2", 2);
+        second.action = Some("merged".into());
+        SessionWriter::create(&dir, &second)
+            .and_then(|w| w.finalize(&second))
+            .expect("write second");
+
+        let entries = index(&dir).expect("index");
+        assert_eq!(entries.len(), 2);
+        let found = find_by_number(&dir, 2).expect("lookup").expect("found");
+        assert_eq!(found.session_id, "abc-s2");
+        assert_eq!(found.codex_session_id, "abc");
+        assert_eq!(found.action.as_deref(), Some("merged"));
+        assert_eq!(count(&dir), 2, "index must not count as a legacy record");
+
+        assert_eq!(parse_session_ref("#12"), Some(12));
+        assert_eq!(parse_session_ref("abc"), None);
+    }
+
+    #[test]
+    fn search_finds_responses_and_commands_newest_first() {
+        let root = TempDir::new("history-search");
+        let dir = root.join("history");
+
+        for (id, n, text, cmd) in [
+            (
+                "a",
+                1,
+                "Refactored the Parser.\nAll good.",
+                "cargo test parser",
+            ),
+            ("b", 2, "Touched docs only.", "ls"),
+            ("c", 3, "Fixed a parser bug\n", "git status"),
+        ] {
+            let rec = record(id, n);
+            let mut writer = SessionWriter::create(&dir, &rec).expect("create session");
+            writer
+                .append_event(&SessionEvent::Message {
+                    content: text.into(),
+                })
+                .expect("append message");
+            writer
+                .append_event(&SessionEvent::Command {
+                    command: cmd.into(),
+                    exit_code: 0,
+                    duration_ms: 5,
+                })
+                .expect("append command");
+            writer.finalize(&rec).expect("finalize");
+        }
+
+        let hits = search(&dir, "PARSER", 10).expect("search");
+        let found: Vec<(usize, &str)> = hits
+            .iter()
+            .map(|h| (h.session_number, h.location.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (3, "response line 1"),
+                (1, "response line 1"),
+                (1, "command 1")
+            ]
+        );
+        assert_eq!(hits[2].snippet, "$ cargo test parser");
+        assert_eq!(search(&dir, "parser", 1).expect("capped").len(), 1);
+    }
+
+    #[test]
+    fn to_markdown_renders_header_commands_and_response() {
+        let record = SessionRecord {
+            session_id: "20260101-000000-s3".into(),
+            session_number: 3,
+            started_at: "2026-01-01T09:30:00Z".parse().expect("timestamp"),
+            duration_secs: 95,
+            model: "gpt-5".into(),
+            prompt_summary: "Fix the tests".into(),
+            response_summary: "Fixed".into(),
+            action: Some("merge".into()),
+            tokens: Some(TokenSnapshot {
+                input_tokens: 1200,
+                output_tokens: 300,
+                ..Default::default()
+            }),
+            command_count: Some(1),
+            est_cost_usd: None,
+        };
+        let events = vec![
+            SessionEvent::Message {
+                content: "Running the tests.\n".into(),
+            },
+            SessionEvent::Command {
+                command: "grep -n `x` src".into(),
+                exit_code: 2,
+                duration_ms: 40,
+            },
+            SessionEvent::SessionComplete {
+                summary: "Fixed the flaky test".into(),
+                action: "merge".into(),
+            },
+        ];
+
+        let md = to_markdown(&record, &events);
+        assert!(md.starts_with("# Session #3\n"), "{md}");
+        assert!(md.contains("- **Date:** 2026-01-01 09:30 UTC\n"), "{md}");
+        assert!(md.contains("- **Duration:** 1m35s\n"), "{md}");
+        assert!(md.contains("1200 input (0 cached) / 300 output"), "{md}");
+        assert!(md.contains("- **Action:** merge\n"), "{md}");
+        assert!(
+            md.contains("## Commands (1)\n\n- `` grep -n `x` src `` — exit 2, 40ms\n"),
+            "{md}"
+        );
+        assert!(md.contains("## Summary\n\nFixed the flaky test\n"), "{md}");
+        assert!(md.ends_with("## Response\n\nRunning the tests.\n"), "{md}");
+    }
+
+    #[test]
+    fn list_picks_up_rewritten_metadata() {
+        let root = TempDir::new("history-cache");
+        let dir = root.join("history");
+
+        let mut rec = record("cache-s1", 1);
+        let writer = SessionWriter::create(&dir, &rec).expect("create");
+        assert_eq!(list(&dir).expect("list")[0].response_summary, "");
+        assert!(list(&dir).expect("cached list")[0].action.is_none());
+
+        rec.response_summary = "finished the parser refactor".into();
+        rec.action = Some("merged".into());
+        writer.finalize(&rec).expect("finalize");
+        let records = list(&dir).expect("list after finalize");
+        assert_eq!(records[0].response_summary, "finished the parser refactor");
+        assert_eq!(records[0].action.as_deref(), Some("merged"));
+    }
+
+    #[test]
+    fn batched_deltas_are_on_disk_after_commands_and_finalize() {
+        let root = TempDir::new("history-flush");
+        let dir = root.join("history");
+        let rec = record("flush-s1", 1);
+        let mut writer = SessionWriter::create(&dir, &rec).expect("create");
+
+        for i in 0..5000 {
+            writer
+                .append_event(&SessionEvent::Message {
+                    content: format!("chunk {i} "),
+                })
+                .expect("append delta");
+        }
+        writer
+            .append_event(&SessionEvent::Command {
+                command: "cargo test".into(),
+                exit_code: 0,
+                duration_ms: 5,
+            })
+            .expect("append command");
+
+        // A command flushes everything buffered before it.
+        let events = load_events(&dir, "flush-s1").expect("load");
+        assert!(matches!(events.last(), Some(SessionEvent::Command { .. })));
+        let expected: String = (0..5000).map(|i| format!("chunk {i} ")).collect();
+        assert_eq!(reconstruct_response(&events), expected);
+
+        for _ in 0..10 {
+            writer
+                .append_event(&SessionEvent::Message {
+                    content: "tail ".into(),
+                })
+                .expect("append delta");
+        }
+        writer.finalize(&rec).expect("finalize");
+        let events = load_events(&dir, "flush-s1").expect("load");
+        assert!(reconstruct_response(&events).ends_with("tail tail "));
+
+        // A quiet stream is written out once the interval has passed.
+        let rec = record("flush-s2", 2);
+        let mut writer = SessionWriter::create(&dir, &rec).expect("create");
+        writer
+            .append_event(&SessionEvent::Message {
+                content: "half a line".into(),
+            })
+            .expect("append delta");
+        writer.flush_if_due().expect("flush");
+        assert!(load_events(&dir, "flush-s2").expect("load").is_empty());
+        if let Some(earlier) = Instant::now().checked_sub(STREAM_FLUSH_INTERVAL) {
+            writer.last_flush = earlier;
+            writer.flush_if_due().expect("flush");
+            let events = load_events(&dir, "flush-s2").expect("load");
+            assert_eq!(reconstruct_response(&events), "half a line");
+        }
+    }
+
+    #[test]
+    fn message_deltas_are_coalesced_without_changing_the_response() {
+        let root = TempDir::new("history-coalesce");
+        let dir = root.join("history");
+        let rec = record("coalesce-s1", 1);
+        let mut writer = SessionWriter::create(&dir, &rec).expect("create");
+
+        let deltas = [
+            "Let",
+            " me",
+            " check",
+            ".\nRunning",
+            " tests",
+            "\n\n",
+            "Done",
+            "!",
+        ];
+        for delta in &deltas[..5] {
+            writer
+                .append_event(&SessionEvent::Message {
+                    content: delta.to_string(),
+                })
+                .expect("append");
+        }
+        writer
+            .append_event(&SessionEvent::Command {
+                command: "cargo test".into(),
+                exit_code: 0,
+                duration_ms: 5,
+            })
+            .expect("append command");
+        for delta in &deltas[5..] {
+            writer
+                .append_event(&SessionEvent::Message {
+                    content: delta.to_string(),
+                })
+                .expect("append");
+        }
+        writer.finalize(&rec).expect("finalize");
+
+        let events = load_events(&dir, "coalesce-s1").expect("load");
+        assert_eq!(reconstruct_response(&events), deltas.concat());
+        let messages: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                SessionEvent::Message { content } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            ["Let me check.\n", "Running tests", "\n\n", "Done!"]
+        );
+        assert!(matches!(events[2], SessionEvent::Command { .. }));
+    }
+
+    #[test]
+    fn load_events_checked_counts_malformed_lines() {
+        let dir = TempDir::new("history-events");
+        fs::create_dir_all(dir.join("s1")).expect("create session dir");
+        fs::write(
+            dir.join("s1").join("events.jsonl"),
+            concat!(
+                "{\"type\":\"message\",\"content\":\"hi\"}\n",
+                "\n",
+                "not json\n",
+                "{\"type\":\"command\",\"command\":\"ls\",\"exit_code\":0,\"duration_ms\":1}\n",
+                "{\"type\":\"message\",\"cont",
+            ),
+        )
+        .expect("write events");
+
+        let loaded = load_events_checked(&dir, "s1").expect("load");
+        assert_eq!(loaded.events.len(), 2);
+        assert_eq!(loaded.malformed, 2);
+        assert_eq!(load_events(&dir, "s1").expect("lenient load").len(), 2);
+    }
+
+    #[test]
+    fn diff_commands_matches_repeated_commands_in_order() {
+        let cmd = |command: &str, exit_code| CommandEntry {
+            command: command.into(),
+            exit_code,
+            duration_ms: 10,
+        };
+        let before = [cmd("cargo build", 0), cmd("cargo test", 0), cmd("ls", 0)];
+        let after = [
+            cmd("cargo build", 0),
+            cmd("cargo build", 0),
+            cmd("cargo test", 101),
+            cmd("git diff", 0),
+        ];
+
+        let diff = diff_commands(&before, &after);
+        let names = |cmds: &[CommandEntry]| -> Vec<String> {
+            cmds.iter().map(|c| c.command.clone()).collect()
+        };
+        assert_eq!(names(&diff.added), ["cargo build", "git diff"]);
+        assert_eq!(names(&diff.removed), ["ls"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0.exit_code, 0);
+        assert_eq!(diff.changed[0].1.exit_code, 101);
+        assert_eq!(diff.unchanged, 1);
+        assert!(diff_commands(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn completion_event_is_found_but_not_part_of_the_response() {
+        let events = vec![
+            SessionEvent::Message {
+                content: "done".into(),
+            },
+            SessionEvent::SessionComplete {
+                summary: "Fixed it".into(),
+                action: "merge".into(),
+            },
+        ];
+        assert_eq!(reconstruct_response(&events), "done");
+        assert_eq!(completion(&events), Some(("Fixed it", "merge")));
+
+        let line = serde_json::to_string(&events[1]).expect("serialize");
+        assert!(line.starts_with(r#"{"type":"session_complete""#), "{line}");
+    }
+
+    #[test]
+    fn session_numbers_stay_distinct_across_sessions_and_deletions() {
+        let root = TempDir::new("history-numbers");
+        let dir = root.join("history");
+
+        // Three sessions of one run, each created before the next is numbered.
+        let mut previous = 0;
+        for _ in 0..3 {
+            let n = next_session_number(&dir, previous);
+            let rec = record(&format!("abc-s{n}"), n);
+            SessionWriter::create(&dir, &rec)
+                .and_then(|w| w.finalize(&rec))
+                .expect("write session");
+            previous = n;
+        }
+        let numbers: Vec<usize> = list(&dir)
+            .expect("list")
+            .iter()
+            .map(|r| r.session_number)
+            .collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+
+        // A deleted session's number is not handed out again.
+        fs::remove_dir_all(dir.join("abc-s2")).expect("remove session 2");
+        assert_eq!(count(&dir), 2);
+        assert_eq!(next_session_number(&dir, 0), 4);
+        // The run's own last number wins when it is ahead of the disk.
+        assert_eq!(next_session_number(&dir, 7), 8);
+    }
+}
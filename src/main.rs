@@ -3,24 +3,38 @@
 //! This module defines all top-level subcommands and delegates each action
 //! to the corresponding runtime/helper module.
 
+mod auth;
 mod config;
+mod doctor;
 mod git;
 mod history;
 mod memory;
+mod notes;
+mod notify;
 mod prompt;
 mod registry;
 mod runner;
 mod skills;
+mod status;
 mod tui;
+mod util;
 mod workspace;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::IsTerminal;
+
+use crate::util::truncate;
 
 #[derive(Parser)]
 /// Top-level CLI arguments parsed by clap.
 #[command(name = "openbot", about = "AI agent loop powered by codex-core")]
 struct Cli {
+    /// Never touch the network; registry commands fail immediately (also OPENBOT_OFFLINE=1)
+    #[arg(long, global = true)]
+    offline: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -30,18 +44,26 @@ struct Cli {
 enum Commands {
     /// Run a bot
     Run {
-        /// Bot name
-        #[arg(short, long)]
-        bot: String,
+        /// Bot name; pass several (comma-separated or repeated) to run them in parallel
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        bot: Vec<String>,
 
         /// Override the bot's instructions
         #[arg(short, long)]
         prompt: Option<String>,
 
+        /// One-off task for the first session, added to the bot's instructions
+        #[arg(long, value_name = "TEXT")]
+        task: Option<String>,
+
         /// Maximum number of iterations (0 = unlimited)
         #[arg(short = 'n', long)]
         max_iterations: Option<u32>,
 
+        /// Run a single session with no sleep (same as -n 1 --sleep 0)
+        #[arg(long, conflicts_with_all = ["max_iterations", "sleep"])]
+        once: bool,
+
         /// Model to use (e.g. o4-mini, gpt-4.1)
         #[arg(short, long)]
         model: Option<String>,
@@ -54,7 +76,11 @@ enum Commands {
         #[arg(short, long)]
         sleep: Option<u64>,
 
-        /// Resume a previous session by ID
+        /// Sandbox mode for this run
+        #[arg(long, value_enum)]
+        sandbox: Option<config::Sandbox>,
+
+        /// Resume a previous session by codex ID or #number (see `openbot history`)
         #[arg(long)]
         resume: Option<String>,
 
@@ -62,9 +88,49 @@ enum Commands {
         #[arg(long)]
         project: Option<String>,
 
+        /// List the bot's existing project workspaces and exit
+        #[arg(long)]
+        list_projects: bool,
+
         /// Disable worktree isolation (run directly in working tree)
         #[arg(long)]
         no_worktree: bool,
+
+        /// Start the worktree from HEAD without copying uncommitted changes
+        #[arg(long, conflicts_with = "no_worktree")]
+        no_copy_dirty: bool,
+
+        /// Keep the worktree directory after the run (remove later with `worktrees clean`)
+        #[arg(long, conflicts_with = "no_worktree")]
+        keep_worktree: bool,
+
+        /// Only print the final summary (no streamed output or commands)
+        #[arg(short, long)]
+        quiet: bool,
+
+        /// Stream the model's reasoning (dimmed) and record it in history
+        #[arg(long)]
+        show_reasoning: bool,
+
+        /// Plan only: read-only sandbox, and the agent describes changes instead of making them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write the final result as JSON to this file ("-" for stdout)
+        #[arg(long, value_name = "PATH")]
+        result_file: Option<std::path::PathBuf>,
+
+        /// Keep a JSON heartbeat of the run's progress in this file
+        #[arg(long, value_name = "PATH")]
+        status_file: Option<std::path::PathBuf>,
+
+        /// Append a timestamped plain-text copy of the output to this file
+        #[arg(long, value_name = "PATH")]
+        log_file: Option<std::path::PathBuf>,
+
+        /// Show the run's settings and wait for Enter before the first session (TUI only)
+        #[arg(long)]
+        start_paused: bool,
     },
 
     /// Manage bots
@@ -84,15 +150,29 @@ enum Commands {
         #[arg(long)]
         project: Option<String>,
 
-        /// Show a specific session by ID
+        /// Show a specific session by ID or #number
         #[arg(long)]
         session: Option<String>,
 
         /// Number of recent sessions to show
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
     },
 
+    /// Manage openbot worktrees and branches in the current repo
+    #[command(subcommand)]
+    Worktrees(WorktreesAction),
+
+    /// Check codex credentials
+    #[command(subcommand)]
+    Auth(AuthAction),
+
+    /// Check the environment openbot needs and suggest fixes
+    Doctor,
+
     /// Manage a bot's memory
     Memory {
         /// Bot name
@@ -122,11 +202,27 @@ enum BotsAction {
         /// Initial instructions for the bot
         #[arg(short, long)]
         prompt: Option<String>,
+        /// Registry skills to install into the new bot (comma-separated owner/repo/skill ids)
+        #[arg(long, value_delimiter = ',')]
+        with_skills: Vec<String>,
     },
     /// Show a bot's config and status
     Show {
         /// Bot name
         name: String,
+        /// Print a JSON object instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Open a bot's config.md in $EDITOR
+    Edit {
+        /// Bot name
+        name: String,
+    },
+    /// Check a bot's config.md for unknown keys and bad values
+    Validate {
+        /// Bot name
+        name: String,
     },
 }
 
@@ -137,14 +233,37 @@ enum SkillsAction {
     List {
         /// Bot name
         bot: String,
+        /// Only show skills with at least one of these tags
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
     },
-    /// Search the skills.sh registry
+    /// Search the skills.sh registry, or installed skills with --installed
     Search {
         /// Search query
         query: String,
         /// Maximum number of results
         #[arg(short, long, default_value = "10")]
         limit: u32,
+        /// Skip this many registry results
+        #[arg(long, default_value = "0", conflicts_with = "page")]
+        offset: u32,
+        /// Show this page of registry results (1-based, --limit per page)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        page: Option<u32>,
+        /// Search installed skills (global, plus the bot's with --bot) instead of the registry
+        #[arg(long)]
+        installed: bool,
+        /// Bot whose skills to include with --installed
+        #[arg(short, long, requires = "installed")]
+        bot: Option<String>,
+        /// Choose a result to install right away (needs a terminal)
+        #[arg(long, conflicts_with = "installed")]
+        pick: bool,
+    },
+    /// Preview a registry skill's metadata without installing it
+    Info {
+        /// Skill identifier (owner/repo/skill-name)
+        skill: String,
     },
     /// Install a skill from the skills.sh registry
     Install {
@@ -156,6 +275,9 @@ enum SkillsAction {
         /// Install for a specific bot
         #[arg(short, long)]
         bot: Option<String>,
+        /// Fetch and show what would be installed, without writing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Remove an installed skill
     Remove {
@@ -170,17 +292,119 @@ enum SkillsAction {
     },
 }
 
+#[derive(Subcommand)]
+/// openbot worktrees subcommands.
+enum WorktreesAction {
+    /// Remove leftover openbot branches, worktree directories, and kept worktrees
+    Clean {
+        /// List what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Only delete branches already merged into the current branch
+        #[arg(long)]
+        merged_only: bool,
+    },
+}
+
+#[derive(Subcommand)]
+/// openbot auth subcommands.
+enum AuthAction {
+    /// Report whether codex credentials are present and valid
+    Status,
+}
+
 #[derive(Subcommand)]
 /// openbot memory subcommands.
 enum MemoryAction {
     /// Show all memory entries and history
     Show,
+    /// Print the value stored under a key
+    Get { key: String },
+    /// List keys, optionally only those under a `/`-separated prefix
+    List {
+        /// Key prefix, e.g. `api` for `api/endpoint` and `api/auth/token`
+        prefix: Option<String>,
+    },
+    /// List keys whose key or value contains a substring (case-insensitive)
+    Search {
+        query: String,
+        /// Also print matching values
+        #[arg(long)]
+        values: bool,
+    },
     /// Set a key-value pair
-    Set { key: String, value: String },
+    Set {
+        key: String,
+        /// Value to store; `-` reads it from stdin
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        value: Option<String>,
+        /// Read the value from a file
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
+    },
     /// Remove a key
     Remove { key: String },
     /// Clear all memory
-    Clear,
+    Clear {
+        /// Skip the confirmation prompt (required when not on a terminal)
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Open the memory file in $EDITOR and save it if it still parses
+    Edit,
+    /// Drop old iteration history, keeping entries
+    Prune {
+        /// Number of most recent iteration records to keep
+        #[arg(long, default_value_t = 20)]
+        keep_history: usize,
+    },
+    /// Write memory as JSON to a file or stdout
+    Export {
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Load memory from a JSON file produced by `export`
+    Import {
+        /// Memory JSON file
+        file: std::path::PathBuf,
+        /// Merge into existing memory; imported keys win (default)
+        #[arg(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Replace existing memory entirely
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Subcommand)]
+/// openbot history subcommands.
+enum HistoryAction {
+    /// Re-render a session's messages and commands from events.jsonl
+    Replay {
+        /// Session ID or #number
+        #[arg(long)]
+        session: String,
+        /// Pace playback like the original run, N times faster (omit to print at once)
+        #[arg(long)]
+        speed: Option<f64>,
+    },
+    /// Show which commands changed between two sessions
+    Diff {
+        /// Earlier session ID or #number
+        a: String,
+        /// Later session ID or #number
+        b: String,
+    },
+    /// Write a session as a Markdown transcript
+    Export {
+        /// Session ID or #number
+        #[arg(long)]
+        session: String,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        out: Option<std::path::PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -194,32 +418,119 @@ async fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let offline = cli.offline || registry::offline_from_env();
 
     match cli.command {
         Commands::Run {
             bot,
             prompt,
+            task,
             max_iterations,
+            once,
             model,
             skip_git_check,
             sleep,
+            sandbox,
             resume,
             project,
+            list_projects,
             no_worktree,
+            no_copy_dirty,
+            keep_worktree,
+            quiet,
+            show_reasoning,
+            dry_run,
+            result_file,
+            status_file,
+            log_file,
+            start_paused,
         } => {
-            // Ensure bot exists.
-            config::ensure_global_dirs()?;
-            config::ensure_bot_dirs(&bot)?;
+            let bots = bot;
+            for (i, bot) in bots.iter().enumerate() {
+                config::validate_bot_name(bot)?;
+                if bots[..i].contains(bot) {
+                    anyhow::bail!("bot '{bot}' is listed more than once");
+                }
+            }
 
-            let cfg = config::BotConfig::load(&bot)?.with_overrides(
-                prompt,
-                max_iterations,
-                model,
-                skip_git_check,
-                sleep,
-            );
+            if list_projects {
+                for bot in &bots {
+                    let slugs = config::list_workspaces(bot)?;
+                    if slugs.is_empty() {
+                        println!("No project workspaces for bot '{bot}' yet.");
+                    } else {
+                        println!("Project workspaces for '{bot}':\n");
+                        for slug in &slugs {
+                            println!("  {slug}");
+                        }
+                    }
+                }
+                return Ok(());
+            }
 
-            runner::run(&bot, cfg, resume, project, no_worktree).await?;
+            if bots.len() > 1 {
+                if resume.is_some() {
+                    anyhow::bail!("--resume takes a single bot");
+                }
+                if result_file.is_some() {
+                    anyhow::bail!("--result-file takes a single bot");
+                }
+                if status_file.is_some() {
+                    anyhow::bail!("--status-file takes a single bot");
+                }
+                if log_file.is_some() {
+                    anyhow::bail!("--log-file takes a single bot");
+                }
+                if std::io::stderr().is_terminal() && !quiet {
+                    anyhow::bail!(
+                        "running several bots needs --quiet or stderr redirected (the interactive view shows one bot)"
+                    );
+                }
+            }
+
+            // Ensure bots exist.
+            config::ensure_global_dirs()?;
+            let cwd = std::env::current_dir().context("reading the working directory")?;
+            let mut configs = Vec::new();
+            for bot in &bots {
+                config::ensure_bot_dirs(bot)?;
+                let mut cfg = config::BotConfig::load_for_project(bot, &cwd)?.with_overrides(
+                    prompt.clone(),
+                    if once { Some(1) } else { max_iterations },
+                    model.clone(),
+                    skip_git_check,
+                    if once { Some(0) } else { sleep },
+                    sandbox,
+                );
+                if show_reasoning {
+                    cfg.show_reasoning = true;
+                }
+                if dry_run {
+                    cfg.sandbox = config::Sandbox::ReadOnly;
+                }
+                configs.push((bot.clone(), cfg));
+            }
+
+            let opts = runner::RunOptions {
+                resume_session: resume,
+                task,
+                project,
+                no_worktree,
+                no_copy_dirty,
+                keep_worktree,
+                quiet,
+                dry_run,
+                result_file,
+                status_file,
+                log_file,
+                start_paused,
+            };
+            if configs.len() == 1 {
+                let (bot, cfg) = configs.remove(0);
+                runner::run(&bot, cfg, opts).await?;
+            } else {
+                runner::run_many(configs, opts).await?;
+            }
         }
 
         Commands::Bots(action) => match action {
@@ -273,7 +584,12 @@ async fn main() -> Result<()> {
                 name,
                 description,
                 prompt,
+                with_skills,
             } => {
+                config::validate_bot_name(&name)?;
+                for skill in &with_skills {
+                    parse_skill_identifier(skill)?;
+                }
                 config::ensure_global_dirs()?;
                 config::ensure_bot_dirs(&name)?;
 
@@ -286,18 +602,136 @@ async fn main() -> Result<()> {
                 }
 
                 let config_path = config::bot_config_path(&name)?;
-                std::fs::write(&config_path, config::serialize_config_md(&cfg))?;
+                util::atomic_write(&config_path, config::serialize_config_md(&cfg))?;
 
                 let bot_dir = config::bot_dir(&name)?;
                 println!("Created bot '{name}' at {}", bot_dir.display());
+
+                if !with_skills.is_empty() {
+                    let registry = registry::Registry::new(offline)?;
+                    let mut failed = Vec::new();
+                    for skill in &with_skills {
+                        if let Err(e) =
+                            install_from_registry(&registry, skill, false, Some(&name)).await
+                        {
+                            failed.push((skill, e));
+                        }
+                    }
+                    if !failed.is_empty() {
+                        eprintln!("\nFailed to install {} skill(s):", failed.len());
+                        for (skill, e) in &failed {
+                            eprintln!("  {skill}: {e:#}");
+                        }
+                        anyhow::bail!(
+                            "bot '{name}' was created, but {} of {} starter skills failed to install",
+                            failed.len(),
+                            with_skills.len()
+                        );
+                    }
+                }
             }
-            BotsAction::Show { name } => {
+            BotsAction::Edit { name } => {
+                config::validate_bot_name(&name)?;
+                config::ensure_global_dirs()?;
+                config::ensure_bot_dirs(&name)?;
+
+                let config_path = config::bot_config_path(&name)?;
+                if !config_path.exists() {
+                    let cfg = config::BotConfig::default();
+                    util::atomic_write(&config_path, config::serialize_config_md(&cfg))?;
+                }
+                util::open_in_editor(&config_path)?;
+
+                config::BotConfig::load(&name).with_context(|| {
+                    format!(
+                        "{} has errors; run `openbot bots edit {name}` to fix them",
+                        config_path.display()
+                    )
+                })?;
+                println!("Saved {}", config_path.display());
+            }
+            BotsAction::Validate { name } => {
+                config::validate_bot_name(&name)?;
+                let config_path = config::bot_config_path(&name)?;
+                if !config_path.exists() {
+                    println!("Bot '{name}' has no config.md; defaults are used.");
+                    return Ok(());
+                }
+                let contents = std::fs::read_to_string(&config_path)
+                    .with_context(|| format!("reading {}", config_path.display()))?;
+                let problems = config::validate_config_md(&contents)
+                    .with_context(|| format!("in {}", config_path.display()))?;
+                if problems.is_empty() {
+                    println!("{} is valid.", config_path.display());
+                } else {
+                    for problem in &problems {
+                        eprintln!("warning: {problem}");
+                    }
+                    anyhow::bail!("{} problem(s) in {}", problems.len(), config_path.display());
+                }
+            }
+            BotsAction::Show { name, json } => {
+                config::validate_bot_name(&name)?;
                 let dir = config::bot_dir(&name)?;
                 if !dir.exists() {
+                    if json {
+                        anyhow::bail!("bot '{name}' does not exist");
+                    }
                     println!("Bot '{name}' does not exist.");
                     return Ok(());
                 }
                 let cfg = config::BotConfig::load(&name)?;
+
+                let config_path = config::bot_config_path(&name)?;
+                let problems = std::fs::read_to_string(&config_path)
+                    .ok()
+                    .and_then(|contents| config::validate_config_md(&contents).ok())
+                    .unwrap_or_default();
+
+                let mem_path = config::bot_memory_path(&name)?;
+                let memory_entries = if mem_path.exists() {
+                    Some(memory::MemoryStore::load(&mem_path)?.memory.entries.len())
+                } else {
+                    None
+                };
+
+                let skill_dirs = config::BotConfig::skill_dirs(&name)?;
+                let mut skills = skills::load_skills(&skill_dirs)?;
+                skills.retain(|skill| skill.has_any_tag(&cfg.skill_tags));
+
+                if json {
+                    let summary = BotSummary {
+                        name: &name,
+                        description: &cfg.description,
+                        directory: &dir,
+                        instructions: &cfg.instructions,
+                        config: ConfigSummary {
+                            max_iterations: cfg.max_iterations,
+                            sleep_secs: cfg.sleep_secs,
+                            model: cfg.model.as_deref(),
+                            sandbox: cfg.sandbox,
+                            merge_strategy: cfg.merge_strategy,
+                            auto_commit: cfg.auto_commit,
+                            branch_template: cfg.branch_template.as_deref(),
+                            skill_tags: &cfg.skill_tags,
+                            include_file_tree: cfg.include_file_tree,
+                            show_reasoning: cfg.show_reasoning,
+                        },
+                        memory_entries: memory_entries.unwrap_or(0),
+                        skills: skills
+                            .iter()
+                            .map(|skill| SkillSummary {
+                                name: &skill.name,
+                                description: &skill.description,
+                                source: skill.source.as_deref().unwrap_or("local"),
+                            })
+                            .collect(),
+                        config_warnings: &problems,
+                    };
+                    println!("{}", serde_json::to_string_pretty(&summary)?);
+                    return Ok(());
+                }
+
                 println!("Bot: {name}");
                 if !cfg.description.is_empty() {
                     println!("  Description: {}", cfg.description);
@@ -306,19 +740,23 @@ async fn main() -> Result<()> {
                 println!("  Instructions: {}", truncate(&cfg.instructions, 80));
                 println!("  Max iterations: {}", cfg.max_iterations);
                 println!("  Sleep: {}s", cfg.sleep_secs);
-                println!("  Sandbox: {}", cfg.sandbox);
+                println!("  Sandbox: {}", cfg.sandbox.as_str());
                 if let Some(ref model) = cfg.model {
                     println!("  Model: {model}");
                 }
 
-                let mem_path = config::bot_memory_path(&name)?;
-                if mem_path.exists() {
-                    let store = memory::MemoryStore::load(&mem_path)?;
-                    println!("  Memory: {} entries", store.memory.entries.len());
+                if !problems.is_empty() {
+                    println!("  Config warnings:");
+                    for problem in &problems {
+                        println!("    - {problem}");
+                    }
+                }
+                if let Some(count) = memory_entries {
+                    println!("  Memory: {count} entries");
+                }
+                if !cfg.skill_tags.is_empty() {
+                    println!("  Skill tags: {}", cfg.skill_tags.join(", "));
                 }
-
-                let skill_dirs = config::BotConfig::skill_dirs(&name)?;
-                let skills = skills::load_skills(&skill_dirs)?;
                 if !skills.is_empty() {
                     println!("  Skills:");
                     for skill in &skills {
@@ -329,11 +767,15 @@ async fn main() -> Result<()> {
         },
 
         Commands::Skills(action) => match action {
-            SkillsAction::List { bot } => {
+            SkillsAction::List { bot, tags } => {
+                config::validate_bot_name(&bot)?;
                 let skill_dirs = config::BotConfig::skill_dirs(&bot)?;
-                let skills = skills::load_skills(&skill_dirs)?;
+                let mut skills = skills::load_skills(&skill_dirs)?;
+                skills.retain(|skill| skill.has_any_tag(&tags));
 
-                if skills.is_empty() {
+                if skills.is_empty() && !tags.is_empty() {
+                    println!("No skills for bot '{bot}' tagged {}.", tags.join(", "));
+                } else if skills.is_empty() {
                     println!("No skills found for bot '{bot}'.");
                     println!("Skill directories:");
                     for dir in &skill_dirs {
@@ -343,56 +785,206 @@ async fn main() -> Result<()> {
                     println!("Skills for '{bot}' ({}):\n", skills.len());
                     for skill in &skills {
                         let origin = skill.source.as_deref().unwrap_or("local");
-                        println!("  {} - {} ({})", skill.name, skill.description, origin);
+                        let tags = if skill.tags.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" [{}]", skill.tags.join(", "))
+                        };
+                        println!(
+                            "  {} - {} ({}){}",
+                            skill.name, skill.description, origin, tags
+                        );
+                        if !skill.allowed_tools.is_empty() {
+                            println!("      allowed tools: {}", skill.allowed_tools.join(", "));
+                        }
+                    }
+                    let with_warnings: Vec<_> =
+                        skills.iter().filter(|s| !s.warnings.is_empty()).collect();
+                    if !with_warnings.is_empty() {
+                        println!("\n{} skill(s) with warnings:", with_warnings.len());
+                        for skill in with_warnings {
+                            println!("  {}: {}", skill.name, skill.warnings.join("; "));
+                        }
                     }
                 }
             }
-            SkillsAction::Search { query, limit } => {
-                let results = registry::search(&query, limit).await?;
+            SkillsAction::Search {
+                query,
+                limit,
+                installed: true,
+                bot,
+                ..
+            } => {
+                let skill_dirs = match bot {
+                    Some(ref bot) => {
+                        config::validate_bot_name(bot)?;
+                        config::BotConfig::skill_dirs(bot)?
+                    }
+                    None => vec![config::global_skills_dir()?],
+                };
+                let skills = skills::load_skills(&skill_dirs)?;
+                let matches = skills::search_installed(&skills, &query);
 
-                if results.skills.is_empty() {
+                if matches.is_empty() {
+                    println!("No installed skills match '{query}'.");
+                } else {
+                    for m in matches.iter().take(limit as usize) {
+                        let origin = m.skill.source.as_deref().unwrap_or("local");
+                        println!("  {} ({})", m.skill.name, origin);
+                        if !m.snippet.is_empty() {
+                            println!("    {}", m.snippet);
+                        }
+                    }
+                    if matches.len() > limit as usize {
+                        println!(
+                            "\n{} more (raise --limit to see them)",
+                            matches.len() - limit as usize
+                        );
+                    }
+                }
+            }
+            SkillsAction::Search {
+                query,
+                limit,
+                offset,
+                page,
+                pick,
+                ..
+            } => {
+                let registry = registry::Registry::new(offline)?;
+                let offset = match page {
+                    Some(page) => (page - 1).saturating_mul(limit),
+                    None => offset,
+                };
+                let results =
+                    tui::with_spinner("Searching registry", registry.search(&query, limit, offset))
+                        .await?;
+
+                if results.skills.is_empty() && offset > 0 {
+                    println!(
+                        "No skills past result {offset} for '{query}' ({} total).",
+                        results.count
+                    );
+                } else if results.skills.is_empty() {
                     println!("No skills found for '{query}'.");
                 } else {
+                    let first = u64::from(offset) + 1;
+                    let last = u64::from(offset) + results.skills.len() as u64;
                     println!(
-                        "Found {} skill{} for '{query}':\n",
+                        "Showing {first}–{last} of {} skill{} for '{query}':\n",
                         results.count,
                         if results.count == 1 { "" } else { "s" }
                     );
+                    if pick && !interactive_terminal() {
+                        eprintln!("--pick needs an interactive terminal; listing results only.");
+                    }
+                    let pick = pick && interactive_terminal();
                     let max_id = results.skills.iter().map(|s| s.id.len()).max().unwrap_or(5);
-                    println!("  {:<max_id$}   {:>10}", "Skill", "Installs");
-                    println!("  {:<max_id$}   {:>10}", "─".repeat(max_id), "─".repeat(10));
-                    for skill in &results.skills {
-                        println!("  {:<max_id$}   {:>10}", skill.id, skill.installs,);
+                    let index_width = if pick {
+                        results.skills.len().to_string().len() + 2
+                    } else {
+                        0
+                    };
+                    println!(
+                        "  {:index_width$}{:<max_id$}   {:>10}",
+                        "", "Skill", "Installs"
+                    );
+                    println!(
+                        "  {:index_width$}{:<max_id$}   {:>10}",
+                        "",
+                        "─".repeat(max_id),
+                        "─".repeat(10)
+                    );
+                    for (i, skill) in results.skills.iter().enumerate() {
+                        let index = if pick {
+                            format!("{}.", i + 1)
+                        } else {
+                            String::new()
+                        };
+                        println!(
+                            "  {index:<index_width$}{:<max_id$}   {:>10}",
+                            skill.id, skill.installs,
+                        );
+                    }
+
+                    if last < results.count {
+                        let next = if offset % limit.max(1) == 0 {
+                            format!("--page {}", offset / limit.max(1) + 2)
+                        } else {
+                            format!("--offset {last}")
+                        };
+                        println!("\nMore results: openbot skills search {query:?} {next}");
+                    }
+                    if pick {
+                        println!();
+                        pick_and_install(&registry, &results.skills).await?;
+                    } else {
+                        println!(
+                            "\nInstall: openbot skills install <id> [--bot <name> | --global]"
+                        );
                     }
-                    println!("\nInstall: openbot skills install <id> [--bot <name> | --global]");
                 }
             }
-            SkillsAction::Install { skill, global, bot } => {
+            SkillsAction::Info { skill } => {
+                let registry = registry::Registry::new(offline)?;
                 let (source, skill_id) = parse_skill_identifier(&skill)?;
-
-                let skill_dir = if global {
-                    config::ensure_global_dirs()?;
-                    config::global_skills_dir()?
-                } else if let Some(ref bot_name) = bot {
-                    config::ensure_bot_dirs(bot_name)?;
-                    config::bot_skills_dir(bot_name)?
-                } else {
-                    anyhow::bail!("specify --global or --bot <name>");
+                let (fetched, listing) = tui::with_spinner(&format!("Fetching {skill}"), async {
+                    tokio::join!(
+                        registry.fetch_skill_md_with_url(&source, &skill_id),
+                        registry.search(&skill_id, 50, 0),
+                    )
+                })
+                .await;
+                let (content, url) = fetched?;
+                let parsed =
+                    skills::parse_skill(&content, std::path::Path::new(&format!("{skill_id}.md")))?;
+                let installs = match listing {
+                    Ok(results) => results
+                        .skills
+                        .iter()
+                        .find(|s| s.id == skill)
+                        .map(|s| s.installs.to_string())
+                        .unwrap_or_else(|| "unknown (not in registry search)".to_string()),
+                    Err(e) => format!("unknown ({e})"),
                 };
 
-                println!("Fetching {skill_id} from {source}...");
-                let content = registry::fetch_skill_md(&source, &skill_id).await?;
-
-                skills::install_skill(&skill_dir, &skill_id, &source, &content)?;
-
-                let scope = if global {
-                    "global".to_string()
+                println!("Skill: {}", parsed.name);
+                if !parsed.description.is_empty() {
+                    println!("  Description: {}", parsed.description);
+                }
+                println!("  Source: {source}");
+                println!("  Installs: {installs}");
+                println!("  URL: {url}");
+                if !parsed.tags.is_empty() {
+                    println!("  Tags: {}", parsed.tags.join(", "));
+                }
+                if !parsed.allowed_tools.is_empty() {
+                    println!("  Allowed tools: {}", parsed.allowed_tools.join(", "));
+                }
+                println!("  Body: {} lines", parsed.body.lines().count());
+                for warning in &parsed.warnings {
+                    println!("  Warning: {warning}");
+                }
+                println!("\nInstall: openbot skills install {skill} [--bot <name> | --global]");
+            }
+            SkillsAction::Install {
+                skill,
+                global,
+                bot,
+                dry_run,
+            } => {
+                let registry = registry::Registry::new(offline)?;
+                if dry_run {
+                    preview_install(&registry, &skill, global, bot.as_deref()).await?;
                 } else {
-                    format!("bot '{}'", bot.unwrap())
-                };
-                println!("Installed skill '{skill_id}' ({scope}).");
+                    install_from_registry(&registry, &skill, global, bot.as_deref()).await?;
+                }
             }
+
             SkillsAction::Remove { name, global, bot } => {
+                if let Some(ref bot_name) = bot {
+                    config::validate_bot_name(bot_name)?;
+                }
                 let skill_dir = if global {
                     config::global_skills_dir()?
                 } else if let Some(ref bot_name) = bot {
@@ -414,17 +1006,68 @@ async fn main() -> Result<()> {
             project,
             session,
             limit,
+            action,
         } => {
-            let slug = project.unwrap_or_else(|| {
-                let cwd = std::env::current_dir().unwrap_or_default();
-                let root = workspace::detect_project_root(&cwd);
-                workspace::slug_from_path(&root)
-            });
+            config::validate_bot_name(&bot)?;
+            // Same workspace a `run` from here would use.
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let default_project = config::BotConfig::load(&bot)
+                .ok()
+                .and_then(|cfg| cfg.default_project);
+            let slug = workspace::choose_slug(
+                project.as_deref(),
+                workspace::slug_from_path(&workspace::detect_project_root(&cwd)),
+                default_project.as_deref(),
+                |slug| runner::workspace_has_history(&bot, slug),
+            )?;
             let history_dir = config::bot_workspace_history_dir(&bot, &slug)?;
 
-            if let Some(ref id) = session {
-                // Show a single session.
-                match history::load(&history_dir, id) {
+            if let Some(HistoryAction::Replay { session, speed }) = action {
+                if speed.is_some_and(|s| s.is_nan() || s <= 0.0) {
+                    anyhow::bail!("--speed must be greater than 0");
+                }
+                let id = resolve_session_id(&history_dir, &slug, session)?;
+                let events = load_events_noting(&history_dir, &id)?;
+                if events.is_empty() {
+                    anyhow::bail!("session {id} has no recorded events");
+                }
+                replay_events(&events, speed).await;
+            } else if let Some(HistoryAction::Diff { a, b }) = action {
+                let mut commands = Vec::new();
+                for session in [a, b] {
+                    let id = resolve_session_id(&history_dir, &slug, session)?;
+                    let record = history::load(&history_dir, &id)
+                        .with_context(|| format!("loading session {id}"))?;
+                    let events = load_events_noting(&history_dir, &id)?;
+                    commands.push((record.session_number, history::extract_commands(&events)));
+                }
+                let (before_num, before) = &commands[0];
+                let (after_num, after) = &commands[1];
+                print_command_diff(
+                    *before_num,
+                    *after_num,
+                    &history::diff_commands(before, after),
+                );
+            } else if let Some(HistoryAction::Export { session, out }) = action {
+                let id = resolve_session_id(&history_dir, &slug, session)?;
+                let record = history::load(&history_dir, &id)
+                    .with_context(|| format!("loading session {id}"))?;
+                let events = load_events_noting(&history_dir, &id)?;
+                let markdown = history::to_markdown(&record, &events);
+                if let Some(path) = out {
+                    util::atomic_write(&path, &markdown)
+                        .with_context(|| format!("writing {}", path.display()))?;
+                    eprintln!(
+                        "Exported session #{} to {}",
+                        record.session_number,
+                        path.display()
+                    );
+                } else {
+                    print!("{markdown}");
+                }
+            } else if let Some(session) = session {
+                let id = resolve_session_id(&history_dir, &slug, session)?;
+                match history::load(&history_dir, &id) {
                     Ok(record) => {
                         println!(
                             "{}",
@@ -432,7 +1075,7 @@ async fn main() -> Result<()> {
                         );
 
                         // Show events from events.jsonl if available.
-                        let events = history::load_events(&history_dir, id).unwrap_or_default();
+                        let events = load_events_noting(&history_dir, &id).unwrap_or_default();
                         if !events.is_empty() {
                             let cmds = history::extract_commands(&events);
                             if !cmds.is_empty() {
@@ -454,6 +1097,9 @@ async fn main() -> Result<()> {
                             if !response.is_empty() {
                                 println!("\nResponse:\n{}", response);
                             }
+                            if let Some((summary, action)) = history::completion(&events) {
+                                println!("\nSession complete (action: {action}):\n{summary}");
+                            }
                         }
                     }
                     Err(e) => {
@@ -496,11 +1142,79 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Auth(AuthAction::Status) => auth::status().await?,
+
+        Commands::Doctor => doctor::run(offline).await?,
+
+        Commands::Worktrees(action) => match action {
+            WorktreesAction::Clean {
+                dry_run,
+                merged_only,
+            } => {
+                let cwd = std::env::current_dir()?;
+                let root = git::resolve_repo_root(&cwd)
+                    .ok_or_else(|| anyhow::anyhow!("not inside a git repository"))?;
+                let verb = if dry_run { "Would remove" } else { "Removed" };
+                let mut worktrees = git::list_openbot_worktrees(&root)?;
+                let mut removed = 0;
+
+                for wt in worktrees.iter().filter(|wt| wt.kept) {
+                    if !dry_run {
+                        git::remove_kept_worktree(&wt.path)?;
+                    }
+                    println!("{verb} kept worktree {}", wt.path.display());
+                    removed += 1;
+                }
+                worktrees.retain(|wt| !wt.kept);
+
+                for wt in worktrees.iter().filter(|wt| wt.prunable) {
+                    println!("{verb} stale worktree record {}", wt.path.display());
+                    removed += 1;
+                }
+                if !dry_run {
+                    git::prune_worktrees(&root)?;
+                }
+
+                for dir in git::orphaned_worktree_dirs(&root, &worktrees)? {
+                    if !dry_run {
+                        std::fs::remove_dir_all(&dir)
+                            .with_context(|| format!("removing {}", dir.display()))?;
+                    }
+                    println!("{verb} orphaned directory {}", dir.display());
+                    removed += 1;
+                }
+
+                for branch in git::list_openbot_branches(&root, merged_only)? {
+                    // A live worktree may belong to a bot that is still running.
+                    if let Some(wt) = worktrees
+                        .iter()
+                        .find(|wt| !wt.prunable && wt.branch.as_deref() == Some(branch.as_str()))
+                    {
+                        println!("Skipping {branch} (checked out at {})", wt.path.display());
+                        continue;
+                    }
+                    if !dry_run {
+                        git::delete_branch(&root, &branch)?;
+                    }
+                    println!("{verb} branch {branch}");
+                    removed += 1;
+                }
+
+                if removed == 0 {
+                    println!("Nothing to clean.");
+                }
+            }
+        },
+
         Commands::Memory {
             bot,
             project,
             action,
         } => {
+            config::validate_bot_name(&bot)?;
+            let project = project
+                .map(|p| workspace::resolve_project_slug(&p))
+                .transpose()?;
             let mem_path = if let Some(ref slug) = project {
                 config::bot_workspace_memory_path(&bot, slug)?
             } else {
@@ -512,10 +1226,62 @@ async fn main() -> Result<()> {
                 MemoryAction::Show => {
                     print!("{}", store.display());
                 }
-                MemoryAction::Set { key, value } => {
-                    store.set(key.clone(), value.clone());
+                MemoryAction::Get { key } => match store.get(&key) {
+                    Some(value) => println!("{}", memory::format_value(value)),
+                    None => anyhow::bail!("key {key} not found"),
+                },
+                MemoryAction::List { prefix } => {
+                    let prefix = prefix.unwrap_or_default();
+                    let keys = store.keys_under(&prefix);
+                    if keys.is_empty() && prefix.is_empty() {
+                        println!("No memory entries.");
+                    } else if keys.is_empty() {
+                        println!("No memory keys under '{prefix}'.");
+                    }
+                    for key in keys {
+                        println!("  {key}");
+                    }
+                }
+                MemoryAction::Search { query, values } => {
+                    let matches = store.search(&query);
+                    if matches.is_empty() {
+                        println!("No memory entries match '{query}'.");
+                    }
+                    for (key, value) in matches {
+                        if values {
+                            let value = memory::format_value(value).replace('\n', "\n    ");
+                            println!("  {key} = {value}");
+                        } else {
+                            println!("  {key}");
+                        }
+                    }
+                }
+                MemoryAction::Set { key, value, file } => {
+                    let inline = value.as_deref().filter(|v| *v != "-");
+                    let value = match (inline, &file) {
+                        (Some(v), _) => v.to_string(),
+                        (None, Some(path)) => std::fs::read_to_string(path)
+                            .with_context(|| format!("reading {}", path.display()))?,
+                        (None, None) => {
+                            let mut buf = String::new();
+                            std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)
+                                .context("reading value from stdin")?;
+                            buf
+                        }
+                    };
+                    // Files and heredocs end with a newline that isn't part of the value.
+                    let value = value.trim_end_matches(['\n', '\r']).to_string();
+                    store = store.with_limits(memory_limits(&bot));
+                    let evicted = store.set(key.clone(), value.clone());
                     store.save()?;
-                    println!("Set {key} = {value}");
+                    if inline.is_some() {
+                        println!("Set {key} = {value}");
+                    } else {
+                        println!("Set {key} ({} bytes)", value.len());
+                    }
+                    for old in evicted {
+                        println!("Evicted {old} (over max_memory_entries)");
+                    }
                 }
                 MemoryAction::Remove { key } => {
                     if store.remove(&key).is_some() {
@@ -525,11 +1291,92 @@ async fn main() -> Result<()> {
                         println!("Key {key} not found");
                     }
                 }
-                MemoryAction::Clear => {
+                MemoryAction::Clear { yes } => {
+                    if !yes {
+                        if !interactive_terminal() {
+                            anyhow::bail!("not a terminal; pass --yes to clear memory");
+                        }
+                        let answer = prompt_line(&format!(
+                            "Delete {} entries and {} iteration records from {}? [y/N] ",
+                            store.memory.entries.len(),
+                            store.memory.history.len(),
+                            mem_path.display()
+                        ))?;
+                        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                            println!("Aborted.");
+                            return Ok(());
+                        }
+                    }
                     store.clear();
                     store.save()?;
                     println!("Memory cleared.");
                 }
+                MemoryAction::Edit => {
+                    let scratch = std::env::temp_dir()
+                        .join(format!("openbot-memory-{bot}-{}.json", std::process::id()));
+                    let json = serde_json::to_string_pretty(&store.memory)
+                        .with_context(|| "serializing memory")?;
+                    util::atomic_write(&scratch, format!("{json}\n"))?;
+                    util::open_in_editor(&scratch)?;
+
+                    let contents = std::fs::read_to_string(&scratch)
+                        .with_context(|| format!("reading {}", scratch.display()))?;
+                    // Keep the scratch file on errors so the edits aren't lost.
+                    let edited = memory::Memory::from_json(&contents).with_context(|| {
+                        format!(
+                            "{} is not valid memory JSON; memory was left unchanged",
+                            scratch.display()
+                        )
+                    })?;
+                    std::fs::remove_file(&scratch).ok();
+                    let (added, changed, removed) = store.apply_edit(edited);
+                    store.save()?;
+                    println!(
+                        "Saved {}: {added} added, {changed} changed, {removed} removed.",
+                        mem_path.display()
+                    );
+                }
+                MemoryAction::Prune { keep_history } => {
+                    let dropped = store.prune_history(keep_history);
+                    store.save()?;
+                    println!(
+                        "Pruned {dropped} iteration record(s); kept {}.",
+                        store.memory.history.len()
+                    );
+                }
+                MemoryAction::Export { out } => {
+                    let json = serde_json::to_string_pretty(&store.memory)
+                        .with_context(|| "serializing memory")?;
+                    if let Some(path) = out {
+                        util::atomic_write(&path, format!("{json}\n"))
+                            .with_context(|| format!("writing {}", path.display()))?;
+                        eprintln!(
+                            "Exported {} entries to {}",
+                            store.memory.entries.len(),
+                            path.display()
+                        );
+                    } else {
+                        println!("{json}");
+                    }
+                }
+                MemoryAction::Import {
+                    file,
+                    merge: _,
+                    replace,
+                } => {
+                    let contents = std::fs::read_to_string(&file)
+                        .with_context(|| format!("reading {}", file.display()))?;
+                    let incoming = memory::Memory::from_json(&contents)
+                        .with_context(|| format!("parsing {}", file.display()))?;
+                    store = store.with_limits(memory_limits(&bot));
+                    let (count, iterations, evicted) = store.import(incoming, replace);
+                    store.save()?;
+                    let mode = if replace { "replaced" } else { "merged" };
+                    println!("Imported {count} entries and {iterations} iterations ({mode}).");
+                    for old in evicted {
+                        println!("Evicted {old} (over max_memory_entries)");
+                    }
+                }
             }
         }
     }
@@ -537,6 +1384,347 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// `bots show --json` output.
+#[derive(Serialize)]
+struct BotSummary<'a> {
+    name: &'a str,
+    description: &'a str,
+    directory: &'a std::path::Path,
+    instructions: &'a str,
+    config: ConfigSummary<'a>,
+    memory_entries: usize,
+    skills: Vec<SkillSummary<'a>>,
+    config_warnings: &'a [String],
+}
+
+/// The resolved config values listed by `bots show --json`.
+#[derive(Serialize)]
+struct ConfigSummary<'a> {
+    max_iterations: u32,
+    sleep_secs: u64,
+    model: Option<&'a str>,
+    sandbox: config::Sandbox,
+    merge_strategy: config::MergeStrategy,
+    auto_commit: bool,
+    branch_template: Option<&'a str>,
+    skill_tags: &'a [String],
+    include_file_tree: bool,
+    show_reasoning: bool,
+}
+
+/// A loaded skill as listed by `bots show --json`.
+#[derive(Serialize)]
+struct SkillSummary<'a> {
+    name: &'a str,
+    description: &'a str,
+    /// Registry repo the skill was installed from, or "local".
+    source: &'a str,
+}
+
+/// Memory limits from the bot's config, for `memory` actions that write.
+/// A config that doesn't load falls back to the built-in limits with a
+/// warning rather than blocking the write.
+fn memory_limits(bot: &str) -> memory::MemoryLimits {
+    config::BotConfig::load(bot)
+        .unwrap_or_else(|e| {
+            eprintln!("warning: using default memory limits; bot config failed to load: {e:#}");
+            config::BotConfig::default()
+        })
+        .memory_limits()
+}
+
+/// Resolve a `--session` argument to a history session ID; `#N` is looked up
+/// in the session index.
+fn resolve_session_id(
+    history_dir: &std::path::Path,
+    slug: &str,
+    session: String,
+) -> Result<String> {
+    match history::parse_session_ref(&session) {
+        Some(n) => match history::find_by_number(history_dir, n)? {
+            Some(entry) => Ok(entry.session_id),
+            None => anyhow::bail!("session #{n} not found (workspace: {slug})"),
+        },
+        None => Ok(session),
+    }
+}
+
+/// Load a session's events, warning on stderr when some lines couldn't be
+/// parsed (usually a stream cut off by a crash).
+fn load_events_noting(
+    history_dir: &std::path::Path,
+    id: &str,
+) -> Result<Vec<history::SessionEvent>> {
+    let loaded = history::load_events_checked(history_dir, id)?;
+    if loaded.malformed > 0 {
+        eprintln!(
+            "warning: {} events in session {id} could not be parsed; the stream may be truncated",
+            loaded.malformed
+        );
+    }
+    Ok(loaded.events)
+}
+
+/// Print a [`history::CommandDiff`] as `+`/`-`/`~` lines.
+fn print_command_diff(before: usize, after: usize, diff: &history::CommandDiff) {
+    let status = |code: i32| {
+        if code == 0 {
+            "ok".to_string()
+        } else {
+            format!("exit {code}")
+        }
+    };
+    println!("Commands in #{before} → #{after}:");
+    if diff.is_empty() {
+        println!("  no differences ({} unchanged)", diff.unchanged);
+        return;
+    }
+    for cmd in &diff.removed {
+        println!("  - {} [{}]", cmd.command, status(cmd.exit_code));
+    }
+    for cmd in &diff.added {
+        println!("  + {} [{}]", cmd.command, status(cmd.exit_code));
+    }
+    for (old, new) in &diff.changed {
+        println!(
+            "  ~ {} [{} → {}]",
+            new.command,
+            status(old.exit_code),
+            status(new.exit_code)
+        );
+    }
+    println!(
+        "\n{} added, {} removed, {} changed, {} unchanged",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.unchanged
+    );
+}
+
+/// Longest single pause during a paced replay.
+const MAX_REPLAY_PAUSE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Nominal time per streamed message chunk at `--speed 1`; the event stream
+/// only records real durations for commands.
+const REPLAY_DELTA_PAUSE: std::time::Duration = std::time::Duration::from_millis(20);
+
+/// Print a recorded session the way `openbot run` showed it, using the same
+/// styled lines as the TUI. With `speed`, commands take their recorded
+/// duration divided by `speed`.
+async fn replay_events(events: &[history::SessionEvent], speed: Option<f64>) {
+    use history::SessionEvent;
+
+    let pause = |d: std::time::Duration| async move {
+        if let Some(speed) = speed {
+            // A tiny speed overflows `Duration`; that is a long pause anyway.
+            let scaled = std::time::Duration::try_from_secs_f64(d.as_secs_f64() / speed)
+                .unwrap_or(MAX_REPLAY_PAUSE);
+            tokio::time::sleep(scaled.min(MAX_REPLAY_PAUSE)).await;
+        }
+    };
+    let print = |state: &mut tui::AppState| {
+        for line in state.take_pending() {
+            println!("{}", tui::line_to_plain(&line));
+        }
+    };
+
+    let mut state = tui::AppState::new();
+    for event in events {
+        match event {
+            SessionEvent::Message { content } => {
+                state.append_delta(content);
+                pause(REPLAY_DELTA_PAUSE).await;
+            }
+            SessionEvent::Command {
+                command,
+                exit_code,
+                duration_ms,
+            } => {
+                state.flush_partial();
+                state.flush_line(tui::styled_command(command));
+                print(&mut state);
+                pause(std::time::Duration::from_millis(*duration_ms)).await;
+                if *exit_code != 0 {
+                    state.flush_line(tui::styled_command_exit(*exit_code));
+                }
+            }
+            SessionEvent::Reasoning { content } => {
+                state.flush_partial();
+                for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                    state.flush_line(tui::styled_reasoning(line));
+                }
+            }
+            SessionEvent::SessionComplete { summary, action } => {
+                state.flush_partial();
+                state.flush_line(tui::styled_empty());
+                state.flush_line(tui::styled_header("### Summary"));
+                state.flush_line(tui::styled_detail("Action:", action));
+                for line in summary.lines() {
+                    state.flush_line(tui::styled_agent(line));
+                }
+            }
+            SessionEvent::TokenCount { .. } => {}
+        }
+        print(&mut state);
+    }
+    state.flush_partial();
+    print(&mut state);
+}
+
+/// Fetch `skill` (owner/repo/skill-name) from the registry and install it
+/// globally or for `bot`.
+async fn install_from_registry(
+    registry: &registry::Registry,
+    skill: &str,
+    global: bool,
+    bot: Option<&str>,
+) -> Result<()> {
+    let (source, skill_id) = parse_skill_identifier(skill)?;
+    let skill_dir = install_dir(global, bot, true)?;
+
+    println!("Fetching {skill_id} from {source}...");
+    let content = tui::with_spinner(
+        "Downloading SKILL.md",
+        registry.fetch_skill_md(&source, &skill_id),
+    )
+    .await?;
+
+    skills::install_skill(&skill_dir, &skill_id, &source, &content)?;
+
+    println!(
+        "Installed skill '{skill_id}' ({}).",
+        install_scope(global, bot)
+    );
+    Ok(())
+}
+
+/// `skills install --dry-run`: fetch the skill and show where it would go,
+/// without creating directories or writing the file.
+async fn preview_install(
+    registry: &registry::Registry,
+    skill: &str,
+    global: bool,
+    bot: Option<&str>,
+) -> Result<()> {
+    let (source, skill_id) = parse_skill_identifier(skill)?;
+    let skill_dir = install_dir(global, bot, false)?;
+
+    println!("Fetching {skill_id} from {source}...");
+    let content = tui::with_spinner(
+        "Downloading SKILL.md",
+        registry.fetch_skill_md(&source, &skill_id),
+    )
+    .await?;
+
+    let path = skill_dir.join(format!("{skill_id}.md"));
+    let parsed = skills::parse_skill(&content, &path)?;
+    println!(
+        "Would install skill '{skill_id}' ({}):",
+        install_scope(global, bot)
+    );
+    println!("  Path:        {}", path.display());
+    if path.exists() {
+        println!("               (replaces the existing file)");
+    }
+    println!("  Name:        {}", parsed.name);
+    println!("  Description: {}", parsed.description);
+    if !parsed.tags.is_empty() {
+        println!("  Tags:        {}", parsed.tags.join(", "));
+    }
+    println!("  Size:        {} bytes", content.len());
+    for warning in &parsed.warnings {
+        println!("  warning: {warning}");
+    }
+    println!("Dry run: nothing was written.");
+    Ok(())
+}
+
+/// Skills directory for an install scope, created if `create` is set.
+fn install_dir(global: bool, bot: Option<&str>, create: bool) -> Result<std::path::PathBuf> {
+    if global {
+        if create {
+            config::ensure_global_dirs()?;
+        }
+        config::global_skills_dir()
+    } else if let Some(bot_name) = bot {
+        config::validate_bot_name(bot_name)?;
+        if create {
+            config::ensure_bot_dirs(bot_name)?;
+        }
+        config::bot_skills_dir(bot_name)
+    } else {
+        anyhow::bail!("specify --global or --bot <name>");
+    }
+}
+
+fn install_scope(global: bool, bot: Option<&str>) -> String {
+    if global {
+        "global".to_string()
+    } else {
+        format!("bot '{}'", bot.unwrap_or_default())
+    }
+}
+
+/// Whether stdin and stdout are both terminals, so we can prompt.
+fn interactive_terminal() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Print `prompt` and read one trimmed line from stdin (empty on EOF).
+fn prompt_line(prompt: &str) -> Result<String> {
+    use std::io::Write;
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .context("reading from stdin")?;
+    Ok(line.trim().to_string())
+}
+
+/// Ask which of the numbered search `results` to install and where, then
+/// install it. An empty answer skips.
+async fn pick_and_install(
+    registry: &registry::Registry,
+    results: &[registry::RegistrySkill],
+) -> Result<()> {
+    let skill = loop {
+        let answer = prompt_line(&format!(
+            "Install which skill? [1-{}, Enter to skip]: ",
+            results.len()
+        ))?;
+        if answer.is_empty() {
+            return Ok(());
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=results.len()).contains(&n) => break &results[n - 1],
+            _ => println!("Enter a number from 1 to {}.", results.len()),
+        }
+    };
+
+    let bots = config::list_bots()?;
+    let choices = if bots.is_empty() {
+        "global".to_string()
+    } else {
+        format!("global, {}", bots.join(", "))
+    };
+    let scope = loop {
+        let answer = prompt_line(&format!("Install '{}' where? [{choices}]: ", skill.id))?;
+        if answer.is_empty() {
+            return Ok(());
+        }
+        if answer == "global" || answer == "g" || bots.contains(&answer) {
+            break answer;
+        }
+        println!("Unknown bot '{answer}'. Choose one of: {choices}.");
+    };
+
+    let global = scope == "global" || scope == "g";
+    let bot = (!global).then_some(scope.as_str());
+    install_from_registry(registry, &skill.id, global, bot).await
+}
+
 /// Parse a skill identifier like "owner/repo/skill-name" into (source, skill_id).
 ///
 /// Examples:
@@ -551,8 +1739,3 @@ fn parse_skill_identifier(id: &str) -> Result<(String, String)> {
     let skill_id = parts[2].to_string();
     Ok((source, skill_id))
 }
-
-/// Return `s` unchanged when short enough, otherwise truncate to `max` bytes.
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
-}
@@ -5,20 +5,75 @@
 //! patterns, or anything else useful between runs.
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use tracing::warn;
+
+use crate::util::{atomic_write, truncate, truncate_with_ellipsis};
+
 /// Persistent key-value memory stored as JSON.
+///
+/// Values are arbitrary JSON; older files with string-only values load
+/// unchanged because a JSON string is itself a valid value.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Memory {
-    pub entries: BTreeMap<String, String>,
+    pub entries: BTreeMap<String, Value>,
+    /// Last write time per key, used to pick eviction victims.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub updated_at: BTreeMap<String, DateTime<Utc>>,
+    /// Compact trail of recent iterations, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<IterationRecord>,
+}
+
+/// One-line summary of a finished iteration, kept alongside memory entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_number: usize,
+    pub prompt_summary: String,
+    pub response_summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+}
+
+/// Bounds applied when entries are written (`0` means unlimited).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryLimits {
+    /// Maximum number of entries; the least recently updated are evicted.
+    pub max_entries: usize,
+    /// Maximum serialized size of a single value; longer values are truncated.
+    pub max_value_bytes: usize,
+    /// Maximum iteration records kept in `history`; the oldest are dropped.
+    pub max_history: usize,
+}
+
+impl Memory {
+    /// Parse an exported memory file, rejecting anything without an `entries` object.
+    pub fn from_json(contents: &str) -> Result<Self> {
+        serde_json::from_str(contents)
+            .with_context(|| "expected a memory export like {\"entries\": {...}}")
+    }
+}
+
+/// Render a memory value for humans: strings as-is, everything else as
+/// pretty-printed JSON.
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
+    }
 }
 
 /// Handle for loading, mutating, and saving memory to disk.
 pub struct MemoryStore {
     path: PathBuf,
     pub memory: Memory,
+    limits: MemoryLimits,
 }
 
 impl MemoryStore {
@@ -33,9 +88,16 @@ impl MemoryStore {
         Ok(Self {
             path: path.to_path_buf(),
             memory,
+            limits: MemoryLimits::default(),
         })
     }
 
+    /// Apply size and count caps to subsequent writes.
+    pub fn with_limits(mut self, limits: MemoryLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Persist current memory state to disk.
     pub fn save(&self) -> Result<()> {
         if let Some(parent) = self.path.parent() {
@@ -44,34 +106,433 @@ impl MemoryStore {
         }
         let json =
             serde_json::to_string_pretty(&self.memory).with_context(|| "serializing memory")?;
-        std::fs::write(&self.path, json).with_context(|| "writing memory file")?;
+        atomic_write(&self.path, json).with_context(|| "writing memory file")?;
         Ok(())
     }
 
-    /// Set or replace a key-value memory entry.
-    pub fn set(&mut self, key: String, value: String) {
-        self.memory.entries.insert(key, value);
+    /// Set or replace a string memory entry.
+    ///
+    /// Returns the keys evicted to stay under the entry cap.
+    pub fn set(&mut self, key: String, value: String) -> Vec<String> {
+        self.set_json(key, Value::String(value))
+    }
+
+    /// Set or replace a memory entry with an arbitrary JSON value.
+    ///
+    /// Values over `max_value_bytes` are truncated (structured values are
+    /// stored as their truncated JSON text). Returns the keys evicted to stay
+    /// under `max_entries`.
+    pub fn set_json(&mut self, key: String, value: Value) -> Vec<String> {
+        let value = self.cap_value(&key, value);
+        self.memory.updated_at.insert(key.clone(), Utc::now());
+        self.memory.entries.insert(key.clone(), value);
+        self.evict_over_cap(&key)
+    }
+
+    /// Append an iteration record, dropping the oldest beyond `max_history`.
+    pub fn add_iteration(&mut self, record: IterationRecord) {
+        self.memory.history.push(record);
+        let max = self.limits.max_history;
+        if max > 0 && self.memory.history.len() > max {
+            let excess = self.memory.history.len() - max;
+            self.memory.history.drain(..excess);
+        }
+    }
+
+    /// Keep only the `keep` most recent iteration records, leaving entries
+    /// alone. Returns how many records were dropped.
+    pub fn prune_history(&mut self, keep: usize) -> usize {
+        let excess = self.memory.history.len().saturating_sub(keep);
+        self.memory.history.drain(..excess);
+        excess
+    }
+
+    /// Look up a single entry.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.memory.entries.get(key)
+    }
+
+    /// Entries whose key or rendered value contains `query`, case-insensitively.
+    pub fn search(&self, query: &str) -> Vec<(&String, &Value)> {
+        let needle = query.to_lowercase();
+        self.memory
+            .entries
+            .iter()
+            .filter(|(k, v)| {
+                k.to_lowercase().contains(&needle)
+                    || format_value(v).to_lowercase().contains(&needle)
+            })
+            .collect()
     }
 
     /// Remove a memory entry by key.
-    pub fn remove(&mut self, key: &str) -> Option<String> {
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.memory.updated_at.remove(key);
         self.memory.entries.remove(key)
     }
 
-    /// Remove all entries.
+    /// Remove all entries and iteration history.
     pub fn clear(&mut self) {
         self.memory.entries.clear();
+        self.memory.updated_at.clear();
+        self.memory.history.clear();
+    }
+
+    /// Replace memory with a hand-edited copy, returning how many entries
+    /// were added, changed, and removed.
+    ///
+    /// New and changed entries are stamped as just updated; timestamps of
+    /// removed keys are dropped.
+    pub fn apply_edit(&mut self, mut edited: Memory) -> (usize, usize, usize) {
+        let old = &self.memory.entries;
+        let removed = old
+            .keys()
+            .filter(|k| !edited.entries.contains_key(*k))
+            .count();
+        let (mut added, mut changed) = (0, 0);
+        let now = Utc::now();
+        for (key, value) in &edited.entries {
+            match old.get(key) {
+                None => added += 1,
+                Some(previous) if previous != value => changed += 1,
+                Some(_) => continue,
+            }
+            edited.updated_at.insert(key.clone(), now);
+        }
+        let entries = &edited.entries;
+        edited.updated_at.retain(|k, _| entries.contains_key(k));
+        self.memory = edited;
+        (added, changed, removed)
+    }
+
+    /// Load an exported memory file, returning how many entries and
+    /// iterations were imported plus any keys evicted by the entry cap.
+    ///
+    /// With `replace`, existing memory is discarded first; otherwise incoming
+    /// keys overwrite existing ones and iterations are merged by time.
+    pub fn import(&mut self, incoming: Memory, replace: bool) -> (usize, usize, Vec<String>) {
+        if replace {
+            self.clear();
+        }
+        let now = Utc::now();
+        let count = incoming.entries.len();
+        for (key, value) in incoming.entries {
+            let value = self.cap_value(&key, value);
+            let stamp = incoming.updated_at.get(&key).copied().unwrap_or(now);
+            self.memory.updated_at.insert(key.clone(), stamp);
+            self.memory.entries.insert(key, value);
+        }
+
+        let iterations = incoming.history.len();
+        let mut history = std::mem::take(&mut self.memory.history);
+        history.extend(incoming.history);
+        history.sort_by_key(|r| r.timestamp);
+        for record in history {
+            self.add_iteration(record);
+        }
+
+        (count, iterations, self.evict_over_cap(""))
+    }
+
+    /// Truncate `value` to the configured byte cap.
+    fn cap_value(&self, key: &str, value: Value) -> Value {
+        let max = self.limits.max_value_bytes;
+        if max == 0 {
+            return value;
+        }
+        let text = match value {
+            Value::String(ref s) if s.len() <= max => return value,
+            Value::String(s) => s,
+            other => {
+                let text = other.to_string();
+                if text.len() <= max {
+                    return other;
+                }
+                text
+            }
+        };
+        warn!(
+            key,
+            bytes = text.len(),
+            max,
+            "truncating oversized memory value"
+        );
+        Value::String(truncate(&text, max).to_string())
+    }
+
+    /// Evict least recently updated entries (never `keep`) until under the cap.
+    fn evict_over_cap(&mut self, keep: &str) -> Vec<String> {
+        let max = self.limits.max_entries;
+        let mut evicted = Vec::new();
+        if max == 0 {
+            return evicted;
+        }
+        while self.memory.entries.len() > max {
+            // Entries without a timestamp predate tracking, so they go first.
+            let Some(oldest) = self
+                .memory
+                .entries
+                .keys()
+                .filter(|k| k.as_str() != keep)
+                .min_by_key(|k| self.memory.updated_at.get(*k))
+                .cloned()
+            else {
+                break;
+            };
+            warn!(key = %oldest, max, "evicting oldest memory entry");
+            self.remove(&oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    /// Keys ordered from most to least recently updated.
+    ///
+    /// Entries without a timestamp predate tracking and sort last.
+    pub fn keys_by_recency(&self) -> Vec<&String> {
+        let mut keys: Vec<&String> = self.memory.entries.keys().collect();
+        keys.sort_by_key(|k| std::cmp::Reverse(self.memory.updated_at.get(*k)));
+        keys
+    }
+
+    /// Keys equal to `prefix` or nested under it (`prefix/...`), in key
+    /// order. An empty prefix matches every key.
+    pub fn keys_under(&self, prefix: &str) -> Vec<&String> {
+        let prefix = prefix.trim_end_matches('/');
+        let nested = format!("{prefix}/");
+        self.memory
+            .entries
+            .keys()
+            .filter(|k| prefix.is_empty() || *k == prefix || k.starts_with(&nested))
+            .collect()
     }
 
     /// Render a human-readable dump for CLI output.
+    ///
+    /// Keys containing `/` are grouped into a tree, one level per segment.
     pub fn display(&self) -> String {
+        let mut out = String::new();
         if self.memory.entries.is_empty() {
-            return "No memory entries.\n".to_string();
+            out.push_str("No memory entries.\n");
         }
-        let mut out = String::new();
+        let mut open: Vec<&str> = Vec::new();
         for (k, v) in &self.memory.entries {
-            out.push_str(&format!("  {k} = {v}\n"));
+            let mut segments: Vec<&str> = k.split('/').collect();
+            let leaf = segments.pop().unwrap_or_default();
+            let shared = open
+                .iter()
+                .zip(&segments)
+                .take_while(|(a, b)| a == b)
+                .count();
+            open.truncate(shared);
+            for segment in &segments[shared..] {
+                out.push_str(&format!("{}{segment}/\n", "  ".repeat(open.len() + 1)));
+                open.push(segment);
+            }
+            let indent = "  ".repeat(open.len() + 1);
+            let value = format_value(v).replace('\n', &format!("\n{indent}  "));
+            out.push_str(&format!("{indent}{leaf} = {value}\n"));
+        }
+        if !self.memory.history.is_empty() {
+            out.push_str(&format!(
+                "\nHistory ({} iterations):\n",
+                self.memory.history.len()
+            ));
+            for it in &self.memory.history {
+                let action = it.action.as_deref().unwrap_or("-");
+                let summary =
+                    truncate_with_ellipsis(it.response_summary.lines().next().unwrap_or(""), 80);
+                out.push_str(&format!(
+                    "  #{:<3} {} [{action}] {summary}\n",
+                    it.session_number,
+                    it.timestamp.format("%Y-%m-%d %H:%M"),
+                ));
+            }
         }
         out
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// An empty store that is never saved.
+    fn store(limits: MemoryLimits) -> MemoryStore {
+        MemoryStore {
+            path: PathBuf::from("unused.json"),
+            memory: Memory::default(),
+            limits,
+        }
+    }
+
+    #[test]
+    fn string_only_memory_files_still_load() {
+        let memory: Memory =
+            serde_json::from_str(r#"{"entries": {"priority": "auth module"}}"#).expect("parse");
+        assert_eq!(memory.entries["priority"], json!("auth module"));
+    }
+
+    #[test]
+    fn display_pretty_prints_structured_values() {
+        let mut store = store(MemoryLimits::default());
+        store.set("note".into(), "plain text".into());
+        store.set_json("flaky".into(), json!(["a", "b"]));
+
+        assert_eq!(
+            store.display(),
+            "  flaky = [\n      \"a\",\n      \"b\"\n    ]\n  note = plain text\n"
+        );
+    }
+
+    #[test]
+    fn hierarchical_keys_list_by_prefix_and_display_as_a_tree() {
+        let mut store = store(MemoryLimits::default());
+        for key in ["api/auth/token", "api/endpoint", "apis", "note"] {
+            store.set(key.into(), "x".into());
+        }
+
+        assert_eq!(
+            store.keys_under("api/"),
+            vec!["api/auth/token", "api/endpoint"]
+        );
+        assert_eq!(store.keys_under("api/auth"), vec!["api/auth/token"]);
+        assert_eq!(store.keys_under("").len(), 4);
+        assert_eq!(
+            store.display(),
+            "  api/\n    auth/\n      token = x\n    endpoint = x\n  apis = x\n  note = x\n"
+        );
+    }
+
+    #[test]
+    fn set_evicts_least_recently_updated_entry() {
+        let mut store = store(MemoryLimits {
+            max_entries: 2,
+            ..MemoryLimits::default()
+        });
+        store.memory = serde_json::from_str(r#"{"entries": {"legacy": "old"}}"#).expect("parse");
+
+        assert!(store.set("a".into(), "1".into()).is_empty());
+        assert_eq!(
+            store.set("b".into(), "2".into()),
+            vec!["legacy".to_string()]
+        );
+        // Rewriting "a" makes "b" the oldest.
+        assert!(store.set("a".into(), "3".into()).is_empty());
+        assert_eq!(store.set("c".into(), "4".into()), vec!["b".to_string()]);
+
+        let keys: Vec<&String> = store.memory.entries.keys().collect();
+        assert_eq!(keys, vec!["a", "c"]);
+        assert_eq!(store.memory.updated_at.len(), 2);
+    }
+
+    #[test]
+    fn set_truncates_oversized_values() {
+        let mut store = store(MemoryLimits {
+            max_value_bytes: 5,
+            ..MemoryLimits::default()
+        });
+
+        store.set("s".into(), "héllo world".into());
+        store.set_json("small".into(), json!([1]));
+        store.set_json("big".into(), json!({"k": "value"}));
+
+        assert_eq!(store.memory.entries["s"], json!("héll"));
+        assert_eq!(store.memory.entries["small"], json!([1]));
+        assert_eq!(store.memory.entries["big"], json!("{\"k\":"));
+    }
+
+    #[test]
+    fn import_merges_or_replaces() {
+        let mut store = store(MemoryLimits::default());
+        store.set("keep".into(), "mine".into());
+        store.set("shared".into(), "mine".into());
+
+        let incoming = Memory::from_json(r#"{"entries": {"shared": "theirs", "new": [1, 2]}}"#)
+            .expect("parse");
+        let (count, iterations, evicted) = store.import(incoming.clone(), false);
+        assert_eq!((count, iterations, evicted.len()), (2, 0, 0));
+        assert_eq!(store.memory.entries["shared"], json!("theirs"));
+        assert_eq!(store.memory.entries["keep"], json!("mine"));
+
+        store.import(incoming, true);
+        let keys: Vec<&String> = store.memory.entries.keys().collect();
+        assert_eq!(keys, vec!["new", "shared"]);
+
+        assert!(Memory::from_json(r#"{"priority": "auth"}"#).is_err());
+        assert!(Memory::from_json(r#"{"entries": ["not", "a", "map"]}"#).is_err());
+    }
+
+    #[test]
+    fn search_matches_keys_and_values_case_insensitively() {
+        let mut store = store(MemoryLimits::default());
+        store.set("Auth_Notes".into(), "uses JWT".into());
+        store.set("deploy".into(), "via GitHub Actions".into());
+        store.set_json("owners".into(), json!({"auth": "alice"}));
+
+        let keys = |q: &str| -> Vec<String> {
+            store
+                .search(q)
+                .into_iter()
+                .map(|(k, _)| k.clone())
+                .collect()
+        };
+        assert_eq!(keys("auth"), vec!["Auth_Notes", "owners"]);
+        assert_eq!(keys("GITHUB"), vec!["deploy"]);
+        assert!(keys("nothing").is_empty());
+        assert_eq!(store.get("deploy"), Some(&json!("via GitHub Actions")));
+        assert_eq!(store.get("missing"), None);
+    }
+
+    #[test]
+    fn add_iteration_keeps_the_most_recent_records() {
+        let mut store = store(MemoryLimits {
+            max_history: 2,
+            ..MemoryLimits::default()
+        });
+        for n in 1..=3 {
+            store.add_iteration(IterationRecord {
+                timestamp: Utc::now(),
+                session_number: n,
+                prompt_summary: "audit".into(),
+                response_summary: format!("session {n}"),
+                action: None,
+            });
+        }
+
+        let numbers: Vec<usize> = store
+            .memory
+            .history
+            .iter()
+            .map(|r| r.session_number)
+            .collect();
+        assert_eq!(numbers, vec![2, 3]);
+        assert!(store.display().contains("History (2 iterations):"));
+
+        assert_eq!(store.prune_history(5), 0);
+        assert_eq!(store.prune_history(1), 1);
+        assert_eq!(store.memory.history[0].session_number, 3);
+    }
+
+    #[test]
+    fn apply_edit_restamps_only_touched_entries() {
+        let mut store = store(MemoryLimits::default());
+        store.memory = serde_json::from_str(
+            r#"{"entries": {"keep": "1", "change": "2", "drop": "3"},
+                "updated_at": {"keep": "2024-01-01T00:00:00Z", "drop": "2024-01-01T00:00:00Z"}}"#,
+        )
+        .expect("parse");
+        let edited = Memory::from_json(
+            r#"{"entries": {"keep": "1", "change": "two", "new": "4"},
+                "updated_at": {"keep": "2024-01-01T00:00:00Z", "drop": "2024-01-01T00:00:00Z"}}"#,
+        )
+        .expect("parse");
+
+        assert_eq!(store.apply_edit(edited), (1, 1, 1));
+        let stamps = &store.memory.updated_at;
+        assert_eq!(stamps["keep"].to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert!(stamps["change"] > stamps["keep"] && stamps.contains_key("new"));
+        assert!(!stamps.contains_key("drop"));
+    }
+}
@@ -0,0 +1,76 @@
+//! Free-form per-workspace notes the agent appends to with `notes_append`.
+//!
+//! Unlike memory, notes are an append-only markdown journal; the prompt only
+//! carries the end of it.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::io::Write;
+use std::path::Path;
+
+/// Most of the notes file injected into the prompt, in bytes.
+pub const PROMPT_NOTES_BYTES: usize = 4096;
+
+/// Append `text` under a timestamp heading, creating the file if needed.
+pub fn append(path: &Path, text: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    let entry = format!(
+        "### {}\n\n{}\n\n",
+        Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        text.trim_end()
+    );
+    file.write_all(entry.as_bytes())
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// The last `max_bytes` of the notes file, starting at a line boundary.
+/// `None` if there are no notes yet.
+pub fn tail(path: &Path, max_bytes: usize) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if text.len() <= max_bytes {
+        return Some(text.to_string());
+    }
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let rest = &text[start..];
+    let rest = rest.find('\n').map_or(rest, |i| rest[i..].trim_start());
+    Some(format!("…(earlier notes omitted)\n{rest}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    #[test]
+    fn tail_keeps_whole_lines_from_the_end() {
+        let dir = TempDir::new("notes");
+        // A missing parent directory is created on the first append.
+        let path = dir.join("workspace").join("notes.md");
+        assert_eq!(tail(&path, 100), None);
+
+        append(&path, "first entry").expect("append");
+        append(&path, "second entry\n").expect("append");
+        let all = tail(&path, 1000).expect("notes");
+        assert!(all.starts_with("### "), "{all}");
+        assert!(all.contains("\n\nfirst entry\n\n### "), "{all}");
+        assert!(all.ends_with("\n\nsecond entry"), "{all}");
+
+        let end = tail(&path, 20).expect("notes");
+        assert_eq!(end, "…(earlier notes omitted)\nsecond entry");
+    }
+}
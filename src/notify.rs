@@ -0,0 +1,95 @@
+//! Completion webhooks for unattended runs.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::time::Duration;
+
+/// How long to wait for the webhook endpoint before giving up.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POST the run result to `url`.
+///
+/// Without a `body_template` the payload itself is sent. Otherwise the
+/// template is sent with each `{{key}}` replaced by the payload's top-level
+/// value (see [`render_body`]).
+pub async fn send(url: &str, body_template: Option<&str>, payload: &Value) -> Result<()> {
+    let body = match body_template {
+        Some(template) => render_body(template, payload),
+        None => serde_json::to_string(payload)?,
+    };
+    let resp = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .timeout(NOTIFY_TIMEOUT)
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("posting to {url}"))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("{url} returned {status}: {}", body.trim());
+    }
+    Ok(())
+}
+
+/// Fill `{{key}}` placeholders from the payload's top-level fields.
+///
+/// Strings are inserted JSON-escaped without surrounding quotes, so a
+/// template can write `"text": "{{summary}}"`. Other values are inserted as
+/// JSON (`null`, numbers, objects). Unknown keys are left as written.
+pub fn render_body(template: &str, payload: &Value) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let key = after[..close].trim();
+        match payload.get(key) {
+            Some(Value::String(s)) => {
+                let quoted = Value::String(s.clone()).to_string();
+                out.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Some(value) => out.push_str(&value.to_string()),
+            None => out.push_str(&rest[open..open + 2 + close + 2]),
+        }
+        rest = &after[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn render_body_escapes_strings_and_keeps_unknown_keys() {
+        let payload = json!({
+            "bot": "mybot",
+            "summary": "Fixed \"login\"\nand tests",
+            "duration_secs": 47,
+            "action": null,
+        });
+        let body = render_body(
+            r#"{"text": "{{bot}}: {{ summary }} ({{duration_secs}}s, {{action}}) {{nope}}"}"#,
+            &payload,
+        );
+        assert_eq!(
+            body,
+            r#"{"text": "mybot: Fixed \"login\"\nand tests (47s, null) {{nope}}"}"#
+        );
+        let parsed: Value = serde_json::from_str(&body).expect("valid json");
+        assert!(
+            parsed["text"]
+                .as_str()
+                .unwrap()
+                .contains("Fixed \"login\"\nand")
+        );
+    }
+}
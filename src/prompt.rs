@@ -1,10 +1,83 @@
 //! Prompt construction utilities used to build each autonomous session input.
 
+use anyhow::{Context, Result};
 use std::path::Path;
 
 use crate::history::SessionRecord;
-use crate::memory::MemoryStore;
+use crate::memory::{MemoryStore, format_value};
 use crate::skills::{Skill, format_skills_section};
+use crate::util::truncate;
+
+/// Placeholders a `prompt.md` template may use.
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "instructions",
+    "status",
+    "layout",
+    "skills",
+    "memory",
+    "notes",
+    "user_input",
+    "history",
+    "guidelines",
+];
+
+/// A user-supplied prompt layout with `{{placeholder}}` slots.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    text: String,
+}
+
+impl PromptTemplate {
+    /// Parse a template, rejecting unknown or unterminated placeholders.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rest = text;
+        while let Some(open) = rest.find("{{") {
+            let after = &rest[open + 2..];
+            let close = after
+                .find("}}")
+                .ok_or_else(|| anyhow::anyhow!("unterminated placeholder in prompt template"))?;
+            let name = after[..close].trim();
+            if !TEMPLATE_PLACEHOLDERS.contains(&name) {
+                anyhow::bail!(
+                    "unknown placeholder {{{{{name}}}}} in prompt template (expected one of: {})",
+                    TEMPLATE_PLACEHOLDERS.join(", ")
+                );
+            }
+            rest = &after[close + 2..];
+        }
+        Ok(Self {
+            text: text.to_string(),
+        })
+    }
+
+    /// Load a template from `path`, or `None` when the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text)
+            .with_context(|| format!("in {}", path.display()))
+            .map(Some)
+    }
+
+    /// Substitute each placeholder with its rendered section.
+    fn render(&self, section: impl Fn(&str) -> String) -> String {
+        let mut out = String::new();
+        let mut rest = self.text.as_str();
+        while let Some(open) = rest.find("{{") {
+            out.push_str(&rest[..open]);
+            let after = &rest[open + 2..];
+            // `parse` guarantees every placeholder is closed and known.
+            let close = after.find("}}").unwrap_or(after.len());
+            out.push_str(section(after[..close].trim()).trim_end());
+            rest = after.get(close + 2..).unwrap_or("");
+        }
+        out.push_str(rest);
+        out
+    }
+}
 
 /// Build the full prompt for one session.
 ///
@@ -13,26 +86,78 @@ use crate::skills::{Skill, format_skills_section};
 ///
 /// `user_input` is text the user typed between sessions (during the sleep
 /// phase) that should be addressed directly this session.
+///
+/// `global_memory` is the bot's project-independent store, merged into the
+/// memory section below workspace entries.
+///
+/// `memory_budget` caps the memory section in bytes (`0` means unlimited);
+/// over budget, only the most recently updated entries are listed.
+///
+/// `project_layout` is a pre-rendered file tree of the working directory.
+///
+/// `bot_skill_dir` is where the agent may save new skills; `None` leaves out
+/// the Skills System docs that teach it how.
+///
+/// `notes` is the (already capped) end of the workspace notes file.
+///
+/// When `template` is set its placeholders are filled with the same sections
+/// the built-in layout uses; otherwise the built-in layout is returned.
 #[allow(clippy::too_many_arguments)]
 pub fn build_prompt(
     instructions: &str,
     skills: &[Skill],
     memory: &MemoryStore,
+    global_memory: Option<&MemoryStore>,
+    memory_budget: usize,
+    notes: Option<&str>,
     recent_history: &[SessionRecord],
     session_num: usize,
-    bot_skill_dir: &Path,
+    bot_skill_dir: Option<&Path>,
     project_context: Option<&str>,
     worktree_info: Option<(&str, &str)>,
     user_input: Option<&str>,
+    project_layout: Option<&str>,
+    template: Option<&PromptTemplate>,
 ) -> String {
-    let mut prompt = String::new();
+    let section = |name: &str| match name {
+        "instructions" => format!("{instructions}\n\n"),
+        "status" => status_section(session_num, project_context, worktree_info),
+        "layout" => match project_layout {
+            Some(tree) if !tree.is_empty() => format!("## Project Layout\n\n```\n{tree}```\n\n"),
+            _ => String::new(),
+        },
+        "skills" => {
+            let skills_section = format_skills_section(skills);
+            if skills_section.is_empty() {
+                skills_section
+            } else {
+                format!("{skills_section}\n")
+            }
+        }
+        "memory" => memory_section(memory, global_memory, memory_budget),
+        "notes" => notes_section(notes),
+        "user_input" => user_input_section(user_input),
+        "history" => history_section(recent_history),
+        "guidelines" => guidelines_section(skills.len(), bot_skill_dir),
+        _ => String::new(),
+    };
 
-    // Base task instructions.
-    prompt.push_str(instructions);
-    prompt.push_str("\n\n");
+    match template {
+        Some(template) => template.render(section),
+        None => TEMPLATE_PLACEHOLDERS
+            .iter()
+            .map(|name| section(name))
+            .collect(),
+    }
+}
 
-    // Session context.
-    prompt.push_str("## Status\n");
+/// Session context: project, session number, and worktree branch.
+fn status_section(
+    session_num: usize,
+    project_context: Option<&str>,
+    worktree_info: Option<(&str, &str)>,
+) -> String {
+    let mut prompt = String::from("## Status\n");
     if let Some(project) = project_context {
         prompt.push_str(&format!("- Project: {project}\n"));
     }
@@ -48,46 +173,126 @@ pub fn build_prompt(
         ));
     }
     prompt.push('\n');
+    prompt
+}
 
-    // Skills section.
-    let skills_section = format_skills_section(skills);
-    if !skills_section.is_empty() {
-        prompt.push_str(&skills_section);
-        prompt.push('\n');
+/// The agent's own key-value store, newest entries first when over `budget`.
+///
+/// Entries from the bot's global store are merged in and marked `(global)`;
+/// a workspace entry wins over a global one with the same key.
+fn memory_section(memory: &MemoryStore, global: Option<&MemoryStore>, budget: usize) -> String {
+    let mut prompt = String::new();
+    let workspace = &memory.memory;
+    let mut entries: Vec<_> = workspace
+        .entries
+        .iter()
+        .map(|(k, v)| (k, v, workspace.updated_at.get(k), false))
+        .collect();
+    if let Some(global) = global {
+        entries.extend(
+            global
+                .memory
+                .entries
+                .iter()
+                .filter(|(k, _)| !workspace.entries.contains_key(*k))
+                .map(|(k, v)| (k, v, global.memory.updated_at.get(k), true)),
+        );
     }
+    if entries.is_empty() {
+        return prompt;
+    }
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    let line = |k: &str, v: &serde_json::Value, is_global: bool| {
+        let value = format_value(v).replace('\n', "\n  ");
+        let tag = if is_global { " (global)" } else { "" };
+        format!("- **{k}**{tag}: {value}\n")
+    };
 
-    // Memory section (agent's own key-value store).
-    if !memory.memory.entries.is_empty() {
-        prompt.push_str("## Memory (from previous sessions)\n\n");
-        for (k, v) in &memory.memory.entries {
-            prompt.push_str(&format!("- **{k}**: {v}\n"));
+    prompt.push_str("## Memory (from previous sessions)\n\n");
+    if entries.iter().any(|e| e.3) {
+        prompt.push_str("Entries marked (global) are shared by all of your projects.\n\n");
+    }
+    let all: String = entries.iter().map(|&(k, v, _, g)| line(k, v, g)).collect();
+    if budget == 0 || all.len() <= budget {
+        prompt.push_str(&all);
+    } else {
+        // Stable, so entries without a timestamp keep key order at the end.
+        entries.sort_by_key(|e| std::cmp::Reverse(e.2));
+        let mut used = 0;
+        let mut shown = 0;
+        for &(k, v, _, g) in &entries {
+            let entry = line(k, v, g);
+            if used + entry.len() > budget {
+                break;
+            }
+            used += entry.len();
+            shown += 1;
+            prompt.push_str(&entry);
         }
-        prompt.push('\n');
+        prompt.push_str(&format!(
+            "- …and {} more (use `memory_get` to read other keys)\n",
+            entries.len() - shown
+        ));
     }
+    prompt.push('\n');
+    prompt
+}
 
-    // User input — the user typed this between sessions and it should be
-    // treated as a direct instruction to address in this session.
+/// The end of the agent's notes journal.
+fn notes_section(notes: Option<&str>) -> String {
+    match notes {
+        Some(notes) => format!(
+            "## Notes (your journal from previous sessions)\n\n{notes}\n\n\
+             Add to it with the `notes_append` tool.\n\n"
+        ),
+        None => String::new(),
+    }
+}
+
+/// Text the user typed between sessions, to be treated as a direct instruction.
+fn user_input_section(user_input: Option<&str>) -> String {
+    let mut prompt = String::new();
     if let Some(input) = user_input {
         prompt.push_str("## User Input\n\n");
         prompt.push_str(
             "The user provided the following input. Address this directly in your response:\n\n",
         );
-        prompt.push_str(&format!("> {input}\n\n"));
+        for line in input.lines() {
+            prompt.push_str(&format!("> {line}\n"));
+        }
+        prompt.push('\n');
     }
+    prompt
+}
 
-    // Recent history section.
+/// Summaries and outcomes of the most recent sessions.
+fn history_section(recent_history: &[SessionRecord]) -> String {
+    let mut prompt = String::new();
     if !recent_history.is_empty() {
         prompt.push_str("### Recent History\n");
         for record in recent_history {
+            // Only the first line of the action; review actions append git hints.
+            let outcome = record
+                .action
+                .as_deref()
+                .and_then(|a| a.lines().next())
+                .map(|a| format!("{} — ", truncate(a.trim(), 100)))
+                .unwrap_or_default();
             prompt.push_str(&format!(
-                "- Session {}: {}\n",
+                "- Session {}: {}{}\n",
                 record.session_number,
+                outcome,
                 truncate(&record.response_summary, 200),
             ));
         }
         prompt.push('\n');
     }
+    prompt
+}
 
+/// Standing rules for autonomous work plus the skills system docs.
+fn guidelines_section(skill_count: usize, bot_skill_dir: Option<&Path>) -> String {
+    let mut prompt = String::new();
     // Instructions.
     prompt.push_str("## Instructions\n");
     prompt.push_str(
@@ -98,13 +303,21 @@ pub fn build_prompt(
     prompt.push_str("- When you are done, call the `session_complete` tool with a summary of what you accomplished\n");
     prompt.push_str(
         "- You can call the `session_history` tool to browse previous sessions in detail. \
-         Use action='list' for an overview or action='view' with session_number to read \
-         the full transcript and commands (shows the end first; increase offset to page backward).\n",
+         Use action='list' for an overview, action='search' with a query to find sessions that \
+         mention something, or action='view' with session_number to read the full transcript \
+         and commands (shows the end first; increase offset to page backward).\n",
+    );
+    prompt.push_str(
+        "- You can call the `skills` tool with action='list' to see every skill, or \
+         action='load' with a name to read one in full.\n",
     );
     prompt.push_str(
         "- Do not stop and ask for clarification — use your best judgment and keep moving\n",
     );
     // Skills documentation.
+    let Some(bot_skill_dir) = bot_skill_dir else {
+        return prompt;
+    };
     prompt.push_str(&format!(
         "\n## Skills System\n\n\
          Skills are reusable markdown workflows loaded into your prompt each session.\n\
@@ -120,14 +333,145 @@ pub fn build_prompt(
          The skill will be loaded automatically in your next session.\n\n\
          **When to create a skill:** If you develop a reusable procedure, debugging technique,\n\
          or workflow pattern that would be useful across sessions, save it as a skill.\n",
-        skills.len(),
+        skill_count,
         bot_skill_dir.display()
     ));
 
     prompt
 }
 
-/// Return a borrowed slice capped at max bytes for prompt summaries.
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn render(template: Option<&PromptTemplate>) -> String {
+        let memory = MemoryStore::load(Path::new("/nonexistent/memory.json")).expect("memory");
+        build_prompt(
+            "Do the task.",
+            &[],
+            &memory,
+            None,
+            0,
+            None,
+            &[],
+            3,
+            Some(&PathBuf::from("/skills")),
+            Some("my-app"),
+            None,
+            Some("focus on tests"),
+            Some("src/\n  main.rs\n"),
+            template,
+        )
+    }
+
+    #[test]
+    fn template_fills_known_placeholders() {
+        let template = PromptTemplate::parse(
+            "{{ user_input }}\n\n{{instructions}}\n{{status}}\n{{memory}}end",
+        )
+        .expect("parse");
+        let prompt = render(Some(&template));
+        assert!(prompt.starts_with("## User Input\n"), "{prompt}");
+        assert!(prompt.contains("> focus on tests\n\nDo the task.\n## Status\n"));
+        assert!(prompt.contains("- Session: 3\n"));
+        assert!(
+            prompt.ends_with("- Project: my-app\n- Session: 3\nend"),
+            "{prompt}"
+        );
+    }
+
+    #[test]
+    fn memory_over_budget_lists_newest_entries() {
+        let mut memory = MemoryStore::load(Path::new("/nonexistent/memory.json")).expect("memory");
+        memory.set("old".into(), "a".repeat(40));
+        memory.set("mid".into(), "b".repeat(40));
+        memory.set("new".into(), "c".repeat(40));
+        let stamp = |s| chrono::DateTime::parse_from_rfc3339(s).unwrap().to_utc();
+        memory
+            .memory
+            .updated_at
+            .insert("old".into(), stamp("2024-01-01T00:00:00Z"));
+        memory
+            .memory
+            .updated_at
+            .insert("mid".into(), stamp("2024-01-02T00:00:00Z"));
+        memory
+            .memory
+            .updated_at
+            .insert("new".into(), stamp("2024-01-03T00:00:00Z"));
+
+        let full = memory_section(&memory, None, 0);
+        assert!(full.contains("**old**") && full.contains("**new**"));
+        assert_eq!(memory_section(&memory, None, full.len()), full);
+
+        let section = memory_section(&memory, None, 110);
+        assert!(
+            section.contains("**new**") && section.contains("**mid**"),
+            "{section}"
+        );
+        assert!(!section.contains("**old**"), "{section}");
+        assert!(
+            section.contains("- …and 1 more (use `memory_get`"),
+            "{section}"
+        );
+    }
+
+    #[test]
+    fn global_memory_is_merged_under_workspace_entries() {
+        let mut memory = MemoryStore::load(Path::new("/nonexistent/memory.json")).expect("memory");
+        let mut global = MemoryStore::load(Path::new("/nonexistent/global.json")).expect("memory");
+        memory.set("style".into(), "tabs".into());
+        global.set("style".into(), "spaces".into());
+        global.set("owner".into(), "alice".into());
+
+        let section = memory_section(&memory, Some(&global), 0);
+        assert!(section.contains("Entries marked (global)"), "{section}");
+        assert!(
+            section.ends_with("- **owner** (global): alice\n- **style**: tabs\n\n"),
+            "{section}"
+        );
+        assert!(!section.contains("spaces"), "{section}");
+    }
+
+    #[test]
+    fn multiline_user_input_is_quoted_line_by_line() {
+        let section = user_input_section(Some("fix the parser\nthen update the docs"));
+        assert!(
+            section.ends_with("> fix the parser\n> then update the docs\n\n"),
+            "{section}"
+        );
+    }
+
+    #[test]
+    fn skills_system_docs_need_a_skill_dir() {
+        let taught = guidelines_section(2, Some(Path::new("/skills")));
+        assert!(taught.contains("## Skills System") && taught.contains("`/skills/`"));
+        let untaught = guidelines_section(2, None);
+        assert!(untaught.starts_with("## Instructions\n"));
+        assert!(!untaught.contains("Skills System"), "{untaught}");
+    }
+
+    #[test]
+    fn template_rejects_unknown_or_unterminated_placeholders() {
+        let err = PromptTemplate::parse("{{instructions}} {{tools}}").expect_err("unknown");
+        assert!(err.to_string().contains("{{tools}}"), "{err}");
+        assert!(PromptTemplate::parse("{{status").is_err());
+    }
+
+    #[test]
+    fn builtin_layout_orders_sections() {
+        let prompt = render(None);
+        let order: Vec<usize> = [
+            "Do the task.",
+            "## Status",
+            "## Project Layout\n\n```\nsrc/\n  main.rs\n```",
+            "## User Input",
+            "## Instructions",
+        ]
+        .iter()
+        .map(|s| prompt.find(s).expect(s))
+        .collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]), "{prompt}");
+    }
 }
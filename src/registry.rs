@@ -1,7 +1,52 @@
 //! Client for the skills.sh registry and GitHub raw content fetching.
+//!
+//! `OPENBOT_REGISTRY_URL` and `OPENBOT_RAW_BASE` point these at a mirror.
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::time::Duration;
+
+/// Default connect and per-request timeout; override with
+/// `OPENBOT_REGISTRY_TIMEOUT` (seconds).
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Default registry API host; override with `OPENBOT_REGISTRY_URL`.
+const DEFAULT_REGISTRY_URL: &str = "https://skills.sh";
+
+/// Default host serving raw `SKILL.md` files; override with `OPENBOT_RAW_BASE`.
+const DEFAULT_RAW_BASE: &str = "https://raw.githubusercontent.com";
+
+/// Base URL of the registry API, without a trailing slash.
+fn registry_url() -> Result<String> {
+    resolve_base_url(
+        "OPENBOT_REGISTRY_URL",
+        std::env::var("OPENBOT_REGISTRY_URL").ok(),
+        DEFAULT_REGISTRY_URL,
+    )
+}
+
+/// Base URL for raw skill files, without a trailing slash.
+fn raw_base() -> Result<String> {
+    resolve_base_url(
+        "OPENBOT_RAW_BASE",
+        std::env::var("OPENBOT_RAW_BASE").ok(),
+        DEFAULT_RAW_BASE,
+    )
+}
+
+/// Use `value` (from env var `var`) if set, else `default`. Rejects values
+/// that aren't absolute http(s) URLs.
+fn resolve_base_url(var: &str, value: Option<String>, default: &str) -> Result<String> {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return Ok(default.to_string());
+    };
+    let url = reqwest::Url::parse(value.trim())
+        .with_context(|| format!("{var}={value:?} is not a valid URL"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!("{var}={value:?} must be an http or https URL");
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
 
 /// A skill returned by the skills.sh search API.
 #[derive(Debug, Deserialize)]
@@ -29,83 +74,260 @@ pub struct SearchResponse {
     pub count: u64,
 }
 
-/// Search the skills.sh registry.
-pub async fn search(query: &str, limit: u32) -> Result<SearchResponse> {
-    let url = format!(
-        "https://skills.sh/api/search?q={}&limit={limit}",
-        urlencoded(query),
-    );
-
-    let resp = reqwest::get(&url)
-        .await
-        .context("requesting skills.sh search API")?;
-
-    let status = resp.status();
-    if !status.is_success() {
-        let body = resp.text().await.unwrap_or_default();
-        anyhow::bail!("skills.sh API returned {status}: {body}");
+/// Returned by registry calls in offline mode.
+#[derive(Debug)]
+pub struct OfflineError;
+
+impl std::fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("offline mode: network access is disabled (--offline or OPENBOT_OFFLINE)")
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+/// Whether `OPENBOT_OFFLINE` asks for offline mode (any value but empty,
+/// `0`, or `false`).
+pub fn offline_from_env() -> bool {
+    std::env::var("OPENBOT_OFFLINE")
+        .is_ok_and(|v| !matches!(v.trim().to_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// Request timeout from `OPENBOT_REGISTRY_TIMEOUT`, falling back to
+/// [`DEFAULT_TIMEOUT`].
+fn timeout_from_env() -> Result<Duration> {
+    match std::env::var("OPENBOT_REGISTRY_TIMEOUT") {
+        Ok(value) if !value.trim().is_empty() => {
+            let secs: u64 = value.trim().parse().with_context(|| {
+                format!("OPENBOT_REGISTRY_TIMEOUT={value:?} is not a whole number of seconds")
+            })?;
+            if secs == 0 {
+                anyhow::bail!("OPENBOT_REGISTRY_TIMEOUT must be at least 1 second");
+            }
+            Ok(Duration::from_secs(secs))
+        }
+        _ => Ok(DEFAULT_TIMEOUT),
     }
+}
 
-    resp.json::<SearchResponse>()
-        .await
-        .context("parsing skills.sh search response")
+/// Registry and raw-content access for one CLI invocation, sharing one HTTP
+/// client (and its connection pool) across calls.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    client: reqwest::Client,
+    timeout: Duration,
+    offline: bool,
 }
 
-/// Fetch a skill's SKILL.md content from GitHub.
-///
-/// Tries the multi-skill repo layout first:
-///   `https://raw.githubusercontent.com/{source}/main/skills/{skill_id}/SKILL.md`
-///
-/// Falls back to single-skill repo root:
-///   `https://raw.githubusercontent.com/{source}/main/SKILL.md`
-pub async fn fetch_skill_md(source: &str, skill_id: &str) -> Result<String> {
-    let client = reqwest::Client::new();
-
-    // Try multi-skill layout first.
-    let multi_url =
-        format!("https://raw.githubusercontent.com/{source}/main/skills/{skill_id}/SKILL.md");
-
-    let resp = client
-        .get(&multi_url)
-        .send()
-        .await
-        .context("fetching SKILL.md from GitHub")?;
-
-    if resp.status().is_success() {
-        return resp.text().await.context("reading SKILL.md body");
+impl Registry {
+    pub fn new(offline: bool) -> Result<Self> {
+        let timeout = timeout_from_env()?;
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .timeout(timeout)
+            .build()
+            .context("building HTTP client")?;
+        Ok(Self {
+            client,
+            timeout,
+            offline,
+        })
     }
 
-    // Fallback: single-skill repo.
-    let single_url = format!("https://raw.githubusercontent.com/{source}/main/SKILL.md");
+    /// GET `url`, turning timeouts and connection failures into errors that
+    /// say which one happened.
+    async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        self.client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e, url))
+    }
 
-    let resp = client
-        .get(&single_url)
-        .send()
-        .await
-        .context("fetching SKILL.md (fallback) from GitHub")?;
+    fn request_error(&self, e: reqwest::Error, url: &str) -> anyhow::Error {
+        if e.is_timeout() {
+            anyhow::anyhow!(
+                "timed out after {}s waiting for {url} (set OPENBOT_REGISTRY_TIMEOUT to wait longer)",
+                self.timeout.as_secs()
+            )
+        } else if e.is_connect() {
+            anyhow::Error::new(e).context(format!("could not connect to {url}"))
+        } else {
+            anyhow::Error::new(e).context(format!("requesting {url}"))
+        }
+    }
 
-    if resp.status().is_success() {
-        return resp.text().await.context("reading SKILL.md body");
+    /// Fail immediately instead of touching the network in offline mode.
+    fn ensure_online(&self) -> Result<()> {
+        if self.offline {
+            return Err(OfflineError.into());
+        }
+        Ok(())
     }
 
-    anyhow::bail!(
-        "could not find SKILL.md for {skill_id} in {source} (tried multi-skill and root layouts)"
-    )
+    /// Check that the registry answers at all, returning its base URL.
+    pub async fn ping(&self) -> Result<String> {
+        self.ensure_online()?;
+        let url = registry_url()?;
+        let resp = self.get(&url).await?;
+        if resp.status().is_server_error() {
+            anyhow::bail!("{url} returned {}", resp.status());
+        }
+        Ok(url)
+    }
+
+    /// Search the skills.sh registry, returning up to `limit` results after
+    /// skipping the first `offset`.
+    ///
+    /// The API has no offset parameter, so pages are cut client-side from a
+    /// `limit + offset` request.
+    pub async fn search(&self, query: &str, limit: u32, offset: u32) -> Result<SearchResponse> {
+        self.ensure_online()?;
+        let url = format!(
+            "{}/api/search?q={}&limit={}",
+            registry_url()?,
+            urlencoded(query),
+            limit.saturating_add(offset),
+        );
+
+        let resp = self.get(&url).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            anyhow::bail!("registry API returned {status}: {body}");
+        }
+
+        let body = resp.text().await.map_err(|e| self.request_error(e, &url))?;
+        let mut results: SearchResponse =
+            serde_json::from_str(&body).context("parsing registry search response")?;
+        let skip = (offset as usize).min(results.skills.len());
+        results.skills.drain(..skip);
+        results.skills.truncate(limit as usize);
+        Ok(results)
+    }
+
+    /// Fetch a skill's SKILL.md content from GitHub.
+    ///
+    /// Tries the multi-skill repo layout first:
+    ///   `https://raw.githubusercontent.com/{source}/main/skills/{skill_id}/SKILL.md`
+    ///
+    /// Falls back to single-skill repo root:
+    ///   `https://raw.githubusercontent.com/{source}/main/SKILL.md`
+    pub async fn fetch_skill_md(&self, source: &str, skill_id: &str) -> Result<String> {
+        self.fetch_skill_md_with_url(source, skill_id)
+            .await
+            .map(|(content, _)| content)
+    }
+
+    /// Like [`Registry::fetch_skill_md`], but also returns the raw URL the file came from.
+    pub async fn fetch_skill_md_with_url(
+        &self,
+        source: &str,
+        skill_id: &str,
+    ) -> Result<(String, String)> {
+        self.ensure_online()?;
+        let raw_base = raw_base()?;
+
+        // Try multi-skill layout first.
+        let multi_url = format!("{raw_base}/{source}/main/skills/{skill_id}/SKILL.md");
+
+        let resp = self.get(&multi_url).await?;
+        if resp.status().is_success() {
+            let content = resp
+                .text()
+                .await
+                .map_err(|e| self.request_error(e, &multi_url))?;
+            return Ok((content, multi_url));
+        }
+        if resp.status().is_server_error() {
+            anyhow::bail!("{multi_url} returned {}", resp.status());
+        }
+
+        // Fallback: single-skill repo.
+        let single_url = format!("{raw_base}/{source}/main/SKILL.md");
+
+        let resp = self.get(&single_url).await?;
+        if resp.status().is_success() {
+            let content = resp
+                .text()
+                .await
+                .map_err(|e| self.request_error(e, &single_url))?;
+            return Ok((content, single_url));
+        }
+        if resp.status().is_server_error() {
+            anyhow::bail!("{single_url} returned {}", resp.status());
+        }
+
+        anyhow::bail!(
+            "could not find SKILL.md for {skill_id} in {source} (tried multi-skill and root layouts)"
+        )
+    }
 }
 
-/// Encode a subset of reserved characters for a query parameter value.
+/// Percent-encode a query parameter value.
+///
+/// Everything except RFC 3986 unreserved characters (`A-Z a-z 0-9 - . _ ~`)
+/// is encoded byte by byte, so multi-byte UTF-8 becomes several `%XX`.
 fn urlencoded(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        match ch {
-            ' ' => out.push_str("%20"),
-            '&' => out.push_str("%26"),
-            '=' => out.push_str("%3D"),
-            '+' => out.push_str("%2B"),
-            '#' => out.push_str("%23"),
-            '%' => out.push_str("%25"),
-            _ => out.push(ch),
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
         }
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_urls_default_validate_and_drop_trailing_slash() {
+        let resolve = |value: Option<&str>| {
+            resolve_base_url(
+                "OPENBOT_RAW_BASE",
+                value.map(String::from),
+                DEFAULT_RAW_BASE,
+            )
+        };
+        assert_eq!(resolve(None).unwrap(), DEFAULT_RAW_BASE);
+        assert_eq!(resolve(Some("  ")).unwrap(), DEFAULT_RAW_BASE);
+        assert_eq!(
+            resolve(Some("https://mirror.example.com/raw/")).unwrap(),
+            "https://mirror.example.com/raw"
+        );
+        assert_eq!(
+            resolve(Some("http://10.0.0.5:8080")).unwrap(),
+            "http://10.0.0.5:8080"
+        );
+
+        let err = resolve(Some("mirror.example.com")).unwrap_err();
+        assert!(format!("{err:#}").contains("OPENBOT_RAW_BASE"), "{err:#}");
+        assert!(resolve(Some("ftp://mirror.example.com")).is_err());
+    }
+
+    #[tokio::test]
+    async fn offline_registry_fails_before_any_request() {
+        let registry = Registry::new(true).expect("client");
+        let err = registry.search("review", 10, 0).await.unwrap_err();
+        assert!(err.downcast_ref::<OfflineError>().is_some(), "{err:#}");
+        let err = registry
+            .fetch_skill_md("obra/superpowers", "brainstorming")
+            .await
+            .unwrap_err();
+        assert!(err.is::<OfflineError>(), "{err:#}");
+    }
+
+    #[test]
+    fn urlencoded_escapes_everything_but_unreserved_characters() {
+        assert_eq!(urlencoded("code-review_v2.~"), "code-review_v2.~");
+        assert_eq!(urlencoded("code review"), "code%20review");
+        assert_eq!(urlencoded("a/b?c=d&e#f+g%"), "a%2Fb%3Fc%3Dd%26e%23f%2Bg%25");
+        assert_eq!(urlencoded("café"), "caf%C3%A9");
+        assert_eq!(urlencoded("日本"), "%E6%97%A5%E6%9C%AC");
+    }
+}
@@ -14,6 +14,7 @@ use codex_protocol::protocol::{
 use codex_protocol::user_input::UserInput;
 use crossterm::event::{KeyCode, KeyModifiers};
 use serde_json::json;
+use std::cell::RefCell;
 use std::io::IsTerminal;
 use std::path::Path;
 use std::sync::Arc;
@@ -21,20 +22,38 @@ use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{error, warn};
 
-use crate::config::BotConfig;
+use crate::config::{BotConfig, MergeStrategy};
 use crate::git::{self, WorktreeGuard, WorktreeInfo};
 use crate::history::{
     self, CommandEntry, SessionEvent, SessionRecord, SessionWriter, TokenSnapshot,
 };
-use crate::memory::MemoryStore;
-use crate::prompt::build_prompt;
+use crate::memory::{IterationRecord, MemoryStore, format_value};
+use crate::notify;
+use crate::prompt::{PromptTemplate, build_prompt};
 use crate::skills::load_skills;
+use crate::status::{RunState, StatusFile};
 use crate::tui::{
     AppState, Tui, TuiEvent, line_to_plain, styled_agent, styled_cmd_output, styled_command,
-    styled_command_exit, styled_detail, styled_empty, styled_header, styled_status,
-    styled_user_input, styled_worked,
+    styled_command_exit, styled_detail, styled_empty, styled_header, styled_reasoning, styled_rule,
+    styled_status, styled_user_input, styled_worked,
 };
-use crate::workspace::{detect_project_root, slug_from_path};
+use crate::util::{atomic_write, truncate_with_ellipsis};
+use crate::workspace::{
+    WorkspaceRegistry, choose_slug, detect_project_root, project_layout, slug_from_path,
+};
+
+/// Directory depth shown in the `## Project Layout` prompt section.
+const LAYOUT_MAX_DEPTH: usize = 3;
+
+/// Maximum entries shown in the `## Project Layout` prompt section.
+const LAYOUT_MAX_ENTRIES: usize = 300;
+
+/// Appended to the bot's instructions for `--dry-run`.
+const DRY_RUN_INSTRUCTIONS: &str = "## Dry Run\n\n\
+This is a dry run. Do not modify files, commit, or run commands that change state; \
+the sandbox is read-only and write attempts will be denied. Inspect the project as \
+needed, then describe your plan: the changes you would make, file by file, and the \
+commands you would run. Call `session_complete` with that plan as the summary.";
 
 /// Build the dynamic tool specs registered with each codex session.
 fn session_tools() -> Vec<DynamicToolSpec> {
@@ -62,17 +81,22 @@ fn session_tools() -> Vec<DynamicToolSpec> {
         },
         DynamicToolSpec {
             name: "session_history".into(),
-            description: "Browse previous session history. Use action='list' for an overview \
-                or action='view' with a session_number to read full transcript and commands. \
-                Supports pagination with offset/limit."
+            description: "Browse previous session history. Use action='list' for an overview, \
+                action='view' with a session_number to read full transcript and commands, \
+                or action='search' with a query to find sessions that mention something. \
+                Supports pagination with offset/limit and direction."
                 .into(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "action": {
                         "type": "string",
-                        "enum": ["list", "view"],
-                        "description": "Action to perform: 'list' shows all sessions, 'view' shows details for a specific session"
+                        "enum": ["list", "view", "search"],
+                        "description": "Action to perform: 'list' shows all sessions, 'view' shows details for a specific session, 'search' finds matching lines across sessions"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Case-insensitive text to look for in past responses and commands (required for 'search' action)"
                     },
                     "session_number": {
                         "type": "integer",
@@ -80,7 +104,12 @@ fn session_tools() -> Vec<DynamicToolSpec> {
                     },
                     "offset": {
                         "type": "integer",
-                        "description": "Line offset for pagination (default 0)"
+                        "description": "Line offset for pagination (default 0). Counts back from the end when direction='backward', forward from the top when direction='forward'"
+                    },
+                    "direction": {
+                        "type": "string",
+                        "enum": ["backward", "forward"],
+                        "description": "Page from the end ('backward', default) or from the top ('forward')"
                     },
                     "limit": {
                         "type": "integer",
@@ -95,40 +124,270 @@ fn session_tools() -> Vec<DynamicToolSpec> {
                 "required": ["action"]
             }),
         },
+        DynamicToolSpec {
+            name: "memory_get".into(),
+            description: "Read entries from your persistent memory. Pass a key to get its value, \
+                or omit it to list every key, most recently updated first. \
+                Use this for keys not shown in the prompt's Memory section."
+                .into(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Memory key to read"
+                    }
+                }
+            }),
+        },
+        DynamicToolSpec {
+            name: "notes_append".into(),
+            description: "Append free-form text to your notes journal for this project. \
+                Entries are timestamped, kept across sessions, and the most recent ones \
+                appear in your prompt under Notes. Use memory for facts you look up by key."
+                .into(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Markdown text to append"
+                    }
+                },
+                "required": ["text"]
+            }),
+        },
+        DynamicToolSpec {
+            name: "skills".into(),
+            description: "Browse your skills. Use action='list' for every skill's name and \
+                description, or action='load' with a name to read that skill's full instructions."
+                .into(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "load"],
+                        "description": "Action to perform: 'list' shows all skills, 'load' returns one skill in full"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Skill name (required for 'load' action)"
+                    }
+                },
+                "required": ["action"]
+            }),
+        },
     ]
 }
 
+/// Line prefix and pending partial line for one bot in a multi-bot run.
+struct LabeledOutput {
+    prefix: String,
+    partial: String,
+}
+
+tokio::task_local! {
+    /// Set by [`run_many`] so each bot's plain output is `[bot]`-prefixed.
+    static LABELED_OUTPUT: RefCell<LabeledOutput>;
+}
+
+/// Timestamped plain-text copy of a run's output for `--log-file`.
+struct Transcript {
+    file: std::fs::File,
+    /// Streamed text not yet ended by a newline.
+    partial: String,
+}
+
+impl Transcript {
+    fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening log file {}", path.display()))?;
+        Ok(Self {
+            file,
+            partial: String::new(),
+        })
+    }
+
+    /// Append each line of `text` with a local timestamp. Write errors are
+    /// ignored so a full disk can't stop the run.
+    fn write_lines(&mut self, text: &str) {
+        use std::io::Write;
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        for line in text.split('\n') {
+            let _ = writeln!(self.file, "[{now}] {line}");
+        }
+    }
+
+    fn push_delta(&mut self, text: &str) {
+        self.partial.push_str(text);
+        while let Some(newline) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=newline).collect();
+            self.write_lines(line.trim_end_matches('\n'));
+        }
+    }
+
+    fn flush_partial(&mut self) {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.write_lines(&line);
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Set by [`run`] when `--log-file` is given.
+    static TRANSCRIPT: RefCell<Transcript>;
+}
+
+/// Apply `f` to this task's transcript, if there is one.
+fn with_transcript(f: impl FnOnce(&mut Transcript)) {
+    let _ = TRANSCRIPT.try_with(|t| f(&mut t.borrow_mut()));
+}
+
+/// Whether this task is one bot of a multi-bot run.
+fn is_labeled() -> bool {
+    LABELED_OUTPUT.try_with(|_| ()).is_ok()
+}
+
+/// Print plain text to stderr, prefixing every line in a multi-bot run.
+fn print_plain(text: &str) {
+    with_transcript(|t| t.write_lines(text));
+    let labeled = LABELED_OUTPUT.try_with(|out| {
+        let prefix = &out.borrow().prefix;
+        for line in text.split('\n') {
+            eprintln!("{prefix}{line}");
+        }
+    });
+    if labeled.is_err() {
+        eprintln!("{text}");
+    }
+}
+
+/// `eprintln!` that goes through [`print_plain`].
+macro_rules! plain {
+    ($($arg:tt)*) => {
+        print_plain(&format!($($arg)*))
+    };
+}
+
 /// Dual-mode output: push a styled line (TUI) or print plain text (piped).
 fn emit_line(state: &mut Option<AppState>, line: ratatui::text::Line<'static>) {
     match state {
-        Some(s) => s.flush_line(line),
-        None => eprintln!("{}", line_to_plain(&line)),
+        Some(s) => {
+            with_transcript(|t| t.write_lines(&line_to_plain(&line)));
+            s.flush_line(line);
+        }
+        None => {
+            emit_flush(state);
+            print_plain(&line_to_plain(&line));
+        }
     }
 }
 
 /// Dual-mode streaming delta: accumulate partial text (TUI) or eprint (piped).
+///
+/// In a multi-bot run deltas are held until a full line is available so
+/// concurrent bots don't interleave mid-line.
 fn emit_delta(state: &mut Option<AppState>, text: &str) {
+    with_transcript(|t| t.push_delta(text));
     match state {
         Some(s) => s.append_delta(text),
-        None => eprint!("{text}"),
+        None => {
+            let labeled = LABELED_OUTPUT.try_with(|out| {
+                let mut out = out.borrow_mut();
+                out.partial.push_str(text);
+                while let Some(newline) = out.partial.find('\n') {
+                    let line: String = out.partial.drain(..=newline).collect();
+                    eprintln!("{}{}", out.prefix, line.trim_end_matches('\n'));
+                }
+            });
+            if labeled.is_err() {
+                eprint!("{text}");
+            }
+        }
     }
 }
 
 /// Flush any partial streaming line (e.g. at end of an agent turn).
 fn emit_flush(state: &mut Option<AppState>) {
-    if let Some(s) = state {
-        s.flush_partial();
+    with_transcript(|t| t.flush_partial());
+    match state {
+        Some(s) => s.flush_partial(),
+        None => {
+            let _ = LABELED_OUTPUT.try_with(|out| {
+                let mut out = out.borrow_mut();
+                if !out.partial.is_empty() {
+                    eprintln!("{}{}", out.prefix, out.partial);
+                    out.partial.clear();
+                }
+            });
+        }
     }
 }
 
+/// Per-invocation options for [`run`] that don't belong in the bot's config.
+#[derive(Debug, Default, Clone)]
+pub struct RunOptions {
+    /// Codex session ID to resume, or `#N` for an openbot session number.
+    pub resume_session: Option<String>,
+    /// One-off task given to the first session as user input.
+    pub task: Option<String>,
+    /// Explicit project workspace slug.
+    pub project: Option<String>,
+    /// Run directly in the working tree instead of an isolated worktree.
+    pub no_worktree: bool,
+    /// Start the worktree from HEAD without copying uncommitted changes.
+    pub no_copy_dirty: bool,
+    /// Leave the worktree directory in place after the run.
+    pub keep_worktree: bool,
+    /// Suppress streamed agent output and per-command lines; events are
+    /// still written to disk and the final summary is still printed.
+    pub quiet: bool,
+    /// Plan without modifying anything: the caller forces a read-only
+    /// sandbox, and escalation requests and completion actions are skipped.
+    pub dry_run: bool,
+    /// Write the final result as a JSON object here (`-` for stdout).
+    pub result_file: Option<std::path::PathBuf>,
+    /// Keep a JSON heartbeat of the run's progress here.
+    pub status_file: Option<std::path::PathBuf>,
+    /// Append a timestamped plain-text copy of the output here.
+    pub log_file: Option<std::path::PathBuf>,
+    /// In the TUI, wait for Enter before the first session.
+    pub start_paused: bool,
+}
+
 /// Run the main agent loop, optionally resuming a previous session.
-pub async fn run(
-    bot_name: &str,
-    config: BotConfig,
-    resume_session: Option<String>,
-    project: Option<String>,
-    no_worktree: bool,
-) -> Result<()> {
+pub async fn run(bot_name: &str, config: BotConfig, opts: RunOptions) -> Result<()> {
+    match opts.log_file {
+        Some(ref path) => {
+            let transcript = RefCell::new(Transcript::open(path)?);
+            TRANSCRIPT
+                .scope(transcript, run_loop(bot_name, config, opts))
+                .await
+        }
+        None => run_loop(bot_name, config, opts).await,
+    }
+}
+
+async fn run_loop(bot_name: &str, config: BotConfig, opts: RunOptions) -> Result<()> {
+    let RunOptions {
+        resume_session,
+        task,
+        project,
+        no_worktree,
+        no_copy_dirty,
+        keep_worktree,
+        quiet,
+        dry_run,
+        result_file,
+        status_file,
+        log_file: _,
+        start_paused,
+    } = opts;
     let skill_dirs = BotConfig::skill_dirs(bot_name)?;
 
     let _codex_home = find_codex_home().with_context(|| "finding codex home")?;
@@ -146,10 +405,58 @@ pub async fn run(
         anyhow::bail!("Not inside a git repository. Use --skip-git-check to run anyway.");
     }
 
+    // Derive a workspace slug from the project root directory name.
+    // Use the original cwd (not the worktree) so worktrees of the same repo
+    // share one workspace. `--project` and `default_project` can redirect it.
+    let project_root = detect_project_root(&cwd_for_check);
+    let workspace_slug = choose_slug(
+        project.as_deref(),
+        slug_from_path(&project_root),
+        config.default_project.as_deref(),
+        |slug| workspace_has_history(bot_name, slug),
+    )?;
+
+    // Catch runs from the wrong directory: warn when this isn't the workspace
+    // the bot last ran in (unless `--project` asked for it).
+    let registry_path = crate::config::bot_workspace_registry_path(bot_name)?;
+    match WorkspaceRegistry::load(&registry_path) {
+        Ok(mut registry) => {
+            let previous = registry.record(&workspace_slug, &project_root);
+            if let Some((last, entry)) = previous
+                && project.is_none()
+            {
+                plain!(
+                    "warning: using workspace '{workspace_slug}', but {bot_name} last ran in '{last}' ({})",
+                    entry.root.display()
+                );
+            }
+            if let Err(e) = registry.save(&registry_path) {
+                warn!("failed to update workspace registry: {e:#}");
+            }
+        }
+        Err(e) => warn!("failed to load workspace registry: {e:#}"),
+    }
+    let history_dir = crate::config::bot_workspace_history_dir(bot_name, &workspace_slug)?;
+    let history_count = history::count(&history_dir);
+
     let worktree: Option<WorktreeInfo> = if !no_worktree {
         if let Some(ref root) = repo_root {
-            let wt =
-                git::create_worktree(root, bot_name).with_context(|| "creating git worktree")?;
+            let branch = git::branch_name(
+                config
+                    .branch_template
+                    .as_deref()
+                    .unwrap_or(git::DEFAULT_BRANCH_TEMPLATE),
+                &git::BranchVars {
+                    bot: bot_name,
+                    slug: &workspace_slug,
+                    session: history::next_session_number(&history_dir, 0),
+                },
+            );
+            let wt = git::create_worktree(root, &branch, !no_copy_dirty, config.init_submodules)
+                .with_context(|| "creating git worktree")?;
+            for warning in &wt.warnings {
+                plain!("warning: {warning}");
+            }
             Some(wt)
         } else {
             None
@@ -159,9 +466,25 @@ pub async fn run(
     };
 
     // Guard removes the worktree directory on exit (keeps the branch).
-    let _worktree_guard = worktree
-        .as_ref()
-        .map(|wt| WorktreeGuard::new(wt.path.clone()));
+    // A kept worktree is locked instead, so `worktrees clean` can find it later.
+    let _worktree_guard = match worktree.as_ref() {
+        Some(wt) if keep_worktree => {
+            if let Err(e) = git::keep_worktree(&wt.path) {
+                plain!("warning: {e:#}");
+            }
+            None
+        }
+        Some(wt) => Some(WorktreeGuard::new(wt.path.clone())),
+        None => None,
+    };
+
+    let status = status_file.map(|path| {
+        StatusFile::start(
+            path,
+            bot_name,
+            worktree.as_ref().map(|wt| wt.branch.clone()),
+        )
+    });
 
     let overrides = ConfigOverrides {
         model: config.model.clone(),
@@ -180,10 +503,10 @@ pub async fn run(
         personality: None,
         compact_prompt: None,
         include_apply_patch_tool: None,
-        show_raw_agent_reasoning: None,
+        show_raw_agent_reasoning: config.show_reasoning.then_some(true),
         tools_web_search_request: None,
         ephemeral: None,
-        additional_writable_roots: Vec::new(),
+        additional_writable_roots: config.writable_roots.clone(),
     };
 
     let codex_config = ConfigBuilder::default()
@@ -192,20 +515,21 @@ pub async fn run(
         .await
         .with_context(|| "building codex config")?;
 
-    // Derive a workspace slug from the project root directory name.
-    // Use the original cwd (not the worktree) so worktrees of the same repo
-    // share one workspace.
-    let workspace_slug = if let Some(ref slug) = project {
-        slug.clone()
-    } else {
-        let project_root = detect_project_root(&cwd_for_check);
-        slug_from_path(&project_root)
-    };
-
     let memory_path = crate::config::bot_workspace_memory_path(bot_name, &workspace_slug)?;
-    let memory = MemoryStore::load(&memory_path).with_context(|| "loading memory")?;
-    let history_dir = crate::config::bot_workspace_history_dir(bot_name, &workspace_slug)?;
-    let history_count = history::count(&history_dir);
+    let global_memory_path = BotConfig::memory_path(bot_name)?;
+    let notes_path = crate::config::bot_workspace_notes_path(bot_name, &workspace_slug)?;
+    let mut memory = MemoryStore::load(&memory_path)
+        .with_context(|| "loading memory")?
+        .with_limits(config.memory_limits());
+    let prompt_template =
+        PromptTemplate::load(&crate::config::bot_prompt_template_path(bot_name)?)?;
+    let mut instructions = config.instructions.clone();
+    if let Some(append) = &config.instructions_append {
+        instructions = format!("{instructions}\n\n{}", append.trim());
+    }
+    if dry_run {
+        instructions = format!("{instructions}\n\n{DRY_RUN_INSTRUCTIONS}");
+    }
 
     let auth_manager = AuthManager::shared(
         codex_config.codex_home.clone(),
@@ -213,6 +537,8 @@ pub async fn run(
         codex_config.cli_auth_credentials_store_mode,
     );
 
+    crate::auth::require_login(&auth_manager).await?;
+
     let thread_manager = Arc::new(ThreadManager::new(
         codex_config.codex_home.clone(),
         auth_manager.clone(),
@@ -220,6 +546,34 @@ pub async fn run(
         codex_config.model_catalog.clone(),
     ));
 
+    // Catch a mistyped model before the first turn rather than mid-run.
+    if let Some(ref model) = config.model {
+        use codex_core::models_manager::manager::RefreshStrategy;
+        let presets = thread_manager
+            .get_models_manager()
+            .list_models(RefreshStrategy::OnlineIfUncached)
+            .await;
+        let known: Vec<String> = presets
+            .into_iter()
+            .flat_map(|preset| [preset.model, preset.id])
+            .collect();
+        check_model(model, &known)?;
+    }
+
+    // `--resume #N` names an openbot session number in this workspace.
+    let resume_session = match resume_session
+        .as_deref()
+        .and_then(history::parse_session_ref)
+    {
+        Some(n) => {
+            let entry = history::find_by_number(&history_dir, n)?.ok_or_else(|| {
+                anyhow::anyhow!("session #{n} not found in workspace '{workspace_slug}'")
+            })?;
+            Some(entry.codex_session_id)
+        }
+        None => resume_session,
+    };
+
     // Start or resume a session.
     let codex_core::NewThread {
         thread_id: _,
@@ -266,9 +620,14 @@ pub async fn run(
 
     let max_sessions = config.max_iterations;
     let sleep_duration = Duration::from_secs(config.sleep_secs);
+    // Sessions in a row that ended on a codex error, and the reason the run
+    // gave up, if it did.
+    let mut consecutive_errors: u32 = 0;
+    let mut gave_up: Option<String> = None;
 
-    // Detect whether we have an interactive terminal.
-    let is_tty = std::io::stderr().is_terminal();
+    // Detect whether we have an interactive terminal. Multi-bot runs always
+    // use plain output.
+    let is_tty = std::io::stderr().is_terminal() && !is_labeled();
 
     // Interactive: ratatui TUI with alternate screen.
     // Non-interactive: plain stderr + line-buffered stdin.
@@ -281,13 +640,15 @@ pub async fn run(
 
     // Fallback line reader for non-interactive (piped) mode.
     let stdin = tokio::io::stdin();
-    let mut stdin_reader = if !is_tty {
+    // Multi-bot runs can't tell which bot a typed line is for, so they ignore stdin.
+    let mut stdin_reader = if !is_tty && !is_labeled() {
         Some(BufReader::new(stdin).lines())
     } else {
         None
     };
 
-    let mut pending_input: Option<String> = None;
+    // `--task` reaches the first session the same way typed input does.
+    let mut pending_input: Option<String> = task;
     let mut last_token_info: Option<TokenUsageInfo> = None;
     let mut last_rate_limits: Option<RateLimitSnapshot> = None;
     let mut worktree_result: Option<String> = None;
@@ -300,38 +661,121 @@ pub async fn run(
     // display "— Worked for Xs —" separators.
     let mut reasoning_start: Option<Instant> = None;
     let mut is_reasoning = false;
+    // Context usage for the status bar, e.g. " | 12.3k/128k, 40% left".
+    let mut token_meter = String::new();
+
+    // `--start-paused` waits here; typed text joins `--task` as input.
+    let mut quit_before_start = false;
+    if start_paused && let Some(ref mut t) = tui {
+        emit_line(&mut state, styled_empty());
+        emit_line(&mut state, styled_rule("Ready"));
+        emit_line(&mut state, styled_empty());
+        emit_line(&mut state, styled_detail("Model:", &default_model));
+        emit_line(&mut state, styled_detail("Workspace:", &workspace_slug));
+        if let Some(ref wt) = worktree {
+            emit_line(&mut state, styled_detail("Branch:", &wt.branch));
+        }
+        emit_line(
+            &mut state,
+            styled_detail("Sandbox:", config.sandbox.as_str()),
+        );
+        let iterations = match max_sessions {
+            0 => "unlimited".to_string(),
+            n => n.to_string(),
+        };
+        emit_line(&mut state, styled_detail("Iterations:", &iterations));
+        emit_line(
+            &mut state,
+            styled_status("paused: press enter to start, or type a message and press enter"),
+        );
+        if let Some(ref mut s) = state {
+            s.status = format!("{default_model} | paused");
+        }
+        match wait_for_start(t, &mut state).await {
+            StartChoice::Start(Some(text)) => {
+                emit_line(&mut state, styled_status(&format!("received: {text}")));
+                pending_input = Some(match pending_input.take() {
+                    Some(task) => format!("{task}\n\n{text}"),
+                    None => text,
+                });
+            }
+            StartChoice::Start(None) => {}
+            StartChoice::Quit => quit_before_start = true,
+        }
+    }
 
-    let session_limit = if max_sessions == 0 {
+    let session_limit = if quit_before_start {
+        0
+    } else if max_sessions == 0 {
         u32::MAX
     } else {
         max_sessions
     };
 
+    // Workspace-wide number of the current session.
+    let mut total_session = 0;
+    // Set when the user quits mid-session; the session is still saved.
+    let mut stop_requested = false;
     'outer: for session_num in 1..=session_limit {
         // Reload skills each session so newly created ones get picked up.
-        let skills = load_skills(&skill_dirs).unwrap_or_else(|e| {
+        let mut skills = load_skills(&skill_dirs).unwrap_or_else(|e| {
             warn!("failed to reload skills: {e}");
             Vec::new()
         });
+        skills.retain(|skill| skill.has_any_tag(&config.skill_tags));
 
-        let total_session = history_count + session_num as usize;
+        // Re-read each session so numbers stay distinct even if another run
+        // is writing to the same workspace.
+        total_session = history::next_session_number(&history_dir, total_session);
 
         let bot_skill_dir = crate::config::bot_skills_dir(bot_name)
             .unwrap_or_else(|_| std::path::PathBuf::from("skills"));
+        // A read-only sandbox can't write the skill file, so don't teach it.
+        let teach_skills =
+            config.prompt_teach_skills && config.sandbox != crate::config::Sandbox::ReadOnly;
         let wt_info = worktree
             .as_ref()
             .map(|wt| (wt.branch.as_str(), wt.base_branch.as_str()));
-        let recent_history = history::recent(&history_dir, 5).unwrap_or_default();
+        let recent_history = if config.prompt_history_count > 0 {
+            history::recent(&history_dir, config.prompt_history_count).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        // Re-list each session so files the agent created show up.
+        let layout = if config.include_file_tree {
+            let dir = worktree
+                .as_ref()
+                .map_or(cwd_for_check.as_path(), |wt| wt.path.as_path());
+            match git::list_files(dir) {
+                Ok(files) => Some(project_layout(&files, LAYOUT_MAX_DEPTH, LAYOUT_MAX_ENTRIES)),
+                Err(e) => {
+                    warn!("failed to list project files: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let notes = crate::notes::tail(&notes_path, crate::notes::PROMPT_NOTES_BYTES);
+        // Reloaded each session to pick up `openbot memory <bot> set` edits.
+        let global_memory = MemoryStore::load(&global_memory_path)
+            .inspect_err(|e| warn!("failed to load global memory: {e}"))
+            .ok();
         let prompt = build_prompt(
-            &config.instructions,
+            &instructions,
             &skills,
             &memory,
+            global_memory.as_ref(),
+            config.max_prompt_memory_bytes,
+            notes.as_deref(),
             &recent_history,
             total_session,
-            &bot_skill_dir,
+            teach_skills.then_some(bot_skill_dir.as_path()),
             Some(&workspace_slug),
             wt_info,
             pending_input.as_deref(),
+            layout.as_deref(),
+            prompt_template.as_ref(),
         );
 
         // Consume pending input once it's included in the prompt.
@@ -344,9 +788,10 @@ pub async fn run(
 
         // Print session header with config details.
         emit_line(&mut state, styled_empty());
+        let started = chrono::Local::now().format("%H:%M");
         emit_line(
             &mut state,
-            styled_header(&format!("## Session {}", total_session)),
+            styled_rule(&format!("Session {total_session} · {started}")),
         );
         emit_line(&mut state, styled_empty());
         emit_line(&mut state, styled_detail("Model:", &default_model));
@@ -375,23 +820,30 @@ pub async fn run(
         // Token/rate snapshots should reflect the current session only.
         last_token_info = None;
         last_rate_limits = None;
+        token_meter.clear();
+        if let Some(ref status) = status {
+            status.update(|s| {
+                s.state = RunState::Running;
+                s.session = total_session;
+                s.tokens = None;
+            });
+        }
 
         let session_start = Instant::now();
-        let session_started_at = Utc::now();
-        let session_record_id = history_session_id(&session_id, total_session);
 
         // Create the event writer to stream events to disk.
         let initial_record = SessionRecord {
-            session_id: session_record_id.clone(),
+            session_id: history_session_id(&session_id, total_session),
             session_number: total_session,
-            started_at: session_started_at,
+            started_at: Utc::now(),
             duration_secs: 0,
             model: default_model.clone(),
-            prompt_summary: truncate_string(&config.instructions, 100),
+            prompt_summary: truncate_with_ellipsis(&config.instructions, 100),
             response_summary: String::new(),
             action: None,
             tokens: None,
             command_count: Some(0),
+            est_cost_usd: None,
         };
         let mut event_writer = SessionWriter::create(&history_dir, &initial_record)
             .map_err(|e| warn!("failed to create event writer: {e}"))
@@ -419,10 +871,13 @@ pub async fn run(
         last_message.clear();
         commands_log.clear();
         let mut session_completed = false;
+        let mut session_error: Option<String> = None;
         let mut completion_summary = String::new();
         let mut completion_action = String::new();
+        let mut flush_tick = tokio::time::interval(history::STREAM_FLUSH_INTERVAL);
+        flush_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-        loop {
+        'events: loop {
             // Listen for codex events, TUI events, and piped stdin.
             let event = tokio::select! {
                 ev = thread.next_event() => ev.with_context(|| "receiving event")?,
@@ -438,11 +893,18 @@ pub async fn run(
                         TuiEvent::Key(key) => {
                             match (key.code, key.modifiers) {
                                 (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
-                                    break 'outer;
+                                    stop_requested = true;
+                                    break 'events;
                                 }
                                 (KeyCode::Char('d'), m) if m.contains(KeyModifiers::CONTROL) => {
                                     let empty = state.as_ref().is_none_or(|s| s.input_buf.is_empty());
-                                    if empty { break 'outer; }
+                                    if empty { stop_requested = true; break 'events; }
+                                }
+                                (KeyCode::Char('o'), m) if m.contains(KeyModifiers::CONTROL) => {
+                                    if let Some(ref mut s) = state {
+                                        let msg = s.toggle_command_output();
+                                        emit_line(&mut state, styled_status(msg));
+                                    }
                                 }
                                 (KeyCode::Esc, _) => {
                                     emit_line(&mut state, styled_status("interrupting..."));
@@ -493,14 +955,14 @@ pub async fn run(
                                     if let Some(start) = reasoning_start {
                                         let thinking = start.elapsed().as_secs();
                                         s.status = format!(
-                                            "{} | session {} | {} | thinking {}s...",
-                                            default_model, total_session, elapsed_str, thinking
+                                            "{} | session {} | {}{} | thinking {}s...",
+                                            default_model, total_session, elapsed_str, token_meter, thinking
                                         );
                                     }
                                 } else {
                                     s.status = format!(
-                                        "{} | session {} | {}",
-                                        default_model, total_session, elapsed_str
+                                        "{} | session {} | {}{}",
+                                        default_model, total_session, elapsed_str, token_meter
                                     );
                                 }
                             }
@@ -537,16 +999,54 @@ pub async fn run(
                             }
                         }
                         Ok(None) => {
-                            break 'outer;
+                            stop_requested = true;
+                            break 'events;
                         }
                         _ => {}
                     }
                     continue;
                 }
+
+                // Write out streamed text even while codex is quiet.
+                _ = flush_tick.tick() => {
+                    if let Some(ref mut w) = event_writer {
+                        w.flush_if_due().ok();
+                    }
+                    continue;
+                }
             };
 
+            if let Some(ref status) = status {
+                status.touch();
+            }
+
             match &event.msg {
                 // ── Reasoning events ──
+                // Complete reasoning blocks are shown (not deltas), since codex
+                // sends the same text through several delta event families.
+                EventMsg::AgentReasoning(codex_protocol::protocol::AgentReasoningEvent {
+                    text,
+                })
+                | EventMsg::AgentReasoningRawContent(
+                    codex_protocol::protocol::AgentReasoningRawContentEvent { text },
+                ) if config.show_reasoning => {
+                    if !is_reasoning {
+                        is_reasoning = true;
+                        reasoning_start = Some(Instant::now());
+                    }
+                    if !quiet {
+                        emit_flush(&mut state);
+                        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                            emit_line(&mut state, styled_reasoning(line));
+                        }
+                    }
+                    if let Some(ref mut w) = event_writer {
+                        w.append_event(&SessionEvent::Reasoning {
+                            content: text.clone(),
+                        })
+                        .ok();
+                    }
+                }
                 EventMsg::AgentReasoningDelta(_)
                 | EventMsg::AgentReasoning(_)
                 | EventMsg::AgentReasoningRawContentDelta(_)
@@ -561,8 +1061,8 @@ pub async fn run(
                         if let Some(start) = reasoning_start {
                             let elapsed = start.elapsed().as_secs();
                             s.status = format!(
-                                "{} | session {} | thinking {}s...",
-                                default_model, total_session, elapsed
+                                "{} | session {}{} | thinking {}s...",
+                                default_model, total_session, token_meter, elapsed
                             );
                         }
                     }
@@ -584,7 +1084,7 @@ pub async fn run(
                     // streaming deltas when available and only use this as a
                     // fallback so the message isn't printed twice.
                     if !msg.message.is_empty() {
-                        if last_message.is_empty() {
+                        if last_message.is_empty() && !quiet {
                             emit_line(&mut state, styled_agent(&msg.message));
                         }
                         last_message = msg.message.clone();
@@ -602,7 +1102,9 @@ pub async fn run(
                         is_reasoning = false;
                     }
                     if !delta.delta.is_empty() {
-                        emit_delta(&mut state, &delta.delta);
+                        if !quiet {
+                            emit_delta(&mut state, &delta.delta);
+                        }
                         last_message.push_str(&delta.delta);
                         if let Some(ref mut w) = event_writer {
                             w.append_event(&SessionEvent::Message {
@@ -616,9 +1118,14 @@ pub async fn run(
                 // ── Command execution ──
                 EventMsg::ExecCommandBegin(cmd) => {
                     emit_flush(&mut state);
-                    emit_line(&mut state, styled_command(&cmd.command.join(" ")));
+                    if !quiet {
+                        emit_line(&mut state, styled_command(&cmd.command.join(" ")));
+                    }
                 }
-                EventMsg::ExecCommandOutputDelta(delta) => {
+                // Plain output always streams it; the TUI only after ctrl-o.
+                EventMsg::ExecCommandOutputDelta(delta)
+                    if !quiet && state.as_ref().is_none_or(|s| s.show_command_output) =>
+                {
                     let text = String::from_utf8_lossy(&delta.chunk);
                     for line in text.lines() {
                         if !line.is_empty() {
@@ -627,7 +1134,7 @@ pub async fn run(
                     }
                 }
                 EventMsg::ExecCommandEnd(result) => {
-                    if result.exit_code != 0 {
+                    if result.exit_code != 0 && !quiet {
                         emit_line(&mut state, styled_command_exit(result.exit_code));
                     }
                     let cmd = result.command.join(" ");
@@ -661,6 +1168,53 @@ pub async fn run(
                         .await
                         .ok();
                 }
+                EventMsg::DynamicToolCallRequest(req) if req.tool == "memory_get" => {
+                    let result_text =
+                        handle_memory_get_tool(&req.arguments, &memory_path, &global_memory_path);
+                    thread
+                        .submit(Op::DynamicToolResponse {
+                            id: req.call_id.clone(),
+                            response: DynamicToolResponse {
+                                content_items: vec![DynamicToolCallOutputContentItem::InputText {
+                                    text: result_text,
+                                }],
+                                success: true,
+                            },
+                        })
+                        .await
+                        .ok();
+                }
+                EventMsg::DynamicToolCallRequest(req) if req.tool == "notes_append" => {
+                    let result_text = handle_notes_append_tool(&req.arguments, &notes_path);
+                    thread
+                        .submit(Op::DynamicToolResponse {
+                            id: req.call_id.clone(),
+                            response: DynamicToolResponse {
+                                content_items: vec![DynamicToolCallOutputContentItem::InputText {
+                                    text: result_text,
+                                }],
+                                success: true,
+                            },
+                        })
+                        .await
+                        .ok();
+                }
+                EventMsg::DynamicToolCallRequest(req) if req.tool == "skills" => {
+                    let result_text =
+                        handle_skills_tool(&req.arguments, &skill_dirs, &config.skill_tags);
+                    thread
+                        .submit(Op::DynamicToolResponse {
+                            id: req.call_id.clone(),
+                            response: DynamicToolResponse {
+                                content_items: vec![DynamicToolCallOutputContentItem::InputText {
+                                    text: result_text,
+                                }],
+                                success: true,
+                            },
+                        })
+                        .await
+                        .ok();
+                }
                 EventMsg::DynamicToolCallRequest(req) if req.tool == "session_complete" => {
                     let summary = req
                         .arguments
@@ -674,6 +1228,13 @@ pub async fn run(
                         .and_then(|v| v.as_str())
                         .unwrap_or("review")
                         .to_string();
+                    if let Some(ref mut w) = event_writer {
+                        w.append_event(&SessionEvent::SessionComplete {
+                            summary: summary.clone(),
+                            action: action.clone(),
+                        })
+                        .ok();
+                    }
                     completion_summary = summary;
                     completion_action = action;
                     session_completed = true;
@@ -700,6 +1261,7 @@ pub async fn run(
                 }
                 EventMsg::Error(e) => {
                     error!("Error from codex: {:?}", e);
+                    session_error = Some(e.message.clone());
                     break;
                 }
                 EventMsg::ExecApprovalRequest(req) => {
@@ -716,6 +1278,16 @@ pub async fn run(
                 EventMsg::TokenCount(tc) => {
                     if let Some(ref info) = tc.info {
                         last_token_info = Some(info.clone());
+                        token_meter = format!(" | {}", context_meter(info));
+                        if let Some(ref mut s) = state {
+                            s.status = format!(
+                                "{} | session {}{}",
+                                default_model, total_session, token_meter
+                            );
+                        }
+                        if let Some(ref status) = status {
+                            status.update(|s| s.tokens = Some(token_snapshot(info)));
+                        }
                         if let Some(ref mut w) = event_writer {
                             let u = &info.total_token_usage;
                             w.append_event(&SessionEvent::TokenCount {
@@ -742,153 +1314,250 @@ pub async fn run(
         // Save session results.
         duration_secs = session_start.elapsed().as_secs();
         response_summary = if completion_summary.is_empty() {
-            truncate_string(&last_message, 500)
+            truncate_with_ellipsis(&last_message, 500)
         } else {
             completion_summary.clone()
         };
 
         let mut session_action: Option<String> = None;
-        if session_completed {
+        if dry_run {
+            response_summary = format!("[dry run] {response_summary}");
+            if session_completed {
+                let result =
+                    format!("dry run, no changes applied (agent chose {completion_action})");
+                session_action = Some(result.clone());
+                worktree_result = Some(result);
+            }
+        } else if session_completed {
             // Post-hook: execute the action the LLM chose.
             if let Some(ref wt) = worktree {
-                let result = match completion_action.as_str() {
-                    "merge" => merge_into_base_branch(&cwd_for_check, &wt.base_branch, &wt.branch),
+                let auto_commit = if config.auto_commit {
+                    let message = auto_commit_message(bot_name, &response_summary);
+                    match git::commit_all(&wt.path, &message) {
+                        Ok(true) => Some("auto-committed uncommitted changes".to_string()),
+                        Ok(false) => None,
+                        Err(e) => Some(format!("auto-commit failed: {e}")),
+                    }
+                } else {
+                    None
+                };
+                // Count before merging, which would bring it to zero.
+                let commits = git::commits_ahead(&wt.path, &wt.base_branch, &wt.branch).ok();
+                let mut hints = String::new();
+                let mut headline = match completion_action.as_str() {
+                    "merge" => merge_into_base_branch(
+                        &cwd_for_check,
+                        &wt.path,
+                        &wt.base_branch,
+                        &wt.branch,
+                        config.merge_strategy,
+                    ),
                     "discard" => {
                         format!("discarded (branch {} kept)", wt.branch)
                     }
                     _ => {
-                        format!(
-                            "review branch {}\n  git log {}..{}\n  git merge {}",
-                            wt.branch, wt.base_branch, wt.branch, wt.branch
-                        )
+                        hints = format!(
+                            "\n  git log {}..{}\n  git merge {}",
+                            wt.base_branch, wt.branch, wt.branch
+                        );
+                        format!("review branch {}", wt.branch)
                     }
                 };
-                session_action = Some(result.clone());
-                worktree_result = Some(result);
+                let notes: Vec<String> = commits
+                    .map(|n| format!("{n} commit{}", if n == 1 { "" } else { "s" }))
+                    .into_iter()
+                    .chain(auto_commit)
+                    .collect();
+                if !notes.is_empty() {
+                    headline.push_str(&format!(" ({})", notes.join(", ")));
+                }
+                // History keeps the one-line headline; the summary adds
+                // where the repo is and how to follow up.
+                session_action = Some(headline.clone());
+                let repo = repo_root.as_deref().unwrap_or(&cwd_for_check);
+                worktree_result = Some(format!("{headline}\n  repo: {}{hints}", repo.display()));
             }
+        } else if let Some(ref wt) = worktree {
+            session_action = Some(format!("not completed (branch {} kept)", wt.branch));
         }
 
-        let tokens = last_token_info.as_ref().map(|info| {
-            let u = &info.total_token_usage;
-            TokenSnapshot {
-                input_tokens: u.input_tokens,
-                cached_input_tokens: u.cached_input_tokens,
-                output_tokens: u.output_tokens,
-                reasoning_output_tokens: u.reasoning_output_tokens,
-                context_window: info.model_context_window,
-            }
-        });
-
-        let record = SessionRecord {
-            session_id: session_record_id,
-            session_number: total_session,
-            started_at: session_started_at,
-            duration_secs,
-            model: default_model.clone(),
-            prompt_summary: truncate_string(&config.instructions, 100),
-            response_summary: response_summary.clone(),
-            action: session_action,
-            tokens,
-            command_count: Some(commands_log.len()),
-        };
+        let record = finish_session_record(
+            SessionRecord {
+                duration_secs,
+                response_summary: response_summary.clone(),
+                action: session_action,
+                ..initial_record
+            },
+            &commands_log,
+            last_token_info.as_ref().map(token_snapshot),
+            &config,
+        );
         if let Some(writer) = event_writer.take() {
             writer.finalize(&record).ok();
         }
 
-        if session_completed {
+        // Reload before appending so CLI edits made during the session survive.
+        match MemoryStore::load(&memory_path) {
+            Ok(fresh) => {
+                memory = fresh.with_limits(config.memory_limits());
+                memory.add_iteration(IterationRecord {
+                    timestamp: Utc::now(),
+                    session_number: total_session,
+                    prompt_summary: record.prompt_summary.clone(),
+                    response_summary: record.response_summary.clone(),
+                    action: record.action.clone(),
+                });
+                if let Err(e) = memory.save() {
+                    warn!("failed to save memory history: {e}");
+                }
+            }
+            Err(e) => warn!("failed to reload memory: {e}"),
+        }
+
+        if session_completed || stop_requested {
             break;
         }
 
+        // Back off on repeated codex errors instead of retrying at full speed.
+        let pause = match session_error {
+            Some(message) => {
+                consecutive_errors += 1;
+                if crate::auth::is_auth_error(&message) {
+                    gave_up = Some(format!(
+                        "codex rejected the credentials ({message}): {}",
+                        crate::auth::LOGIN_HINT
+                    ));
+                    break;
+                }
+                if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                    gave_up = Some(format!(
+                        "codex failed {consecutive_errors} sessions in a row; last error: {message}"
+                    ));
+                    break;
+                }
+                let pause = error_backoff(config.sleep_secs, consecutive_errors);
+                emit_line(
+                    &mut state,
+                    styled_status(&format!(
+                        "codex error ({consecutive_errors} in a row): {message}; retrying in {}s",
+                        pause.as_secs()
+                    )),
+                );
+                pause
+            }
+            None => {
+                consecutive_errors = 0;
+                sleep_duration
+            }
+        };
+
         if session_num >= session_limit {
             break;
         }
 
-        // Sleep between sessions, wake on user input or ctrl-c.
-        if config.sleep_secs > 0 {
+        // Sleep between sessions. Enter wakes early (queueing any typed text);
+        // ctrl-c stops the run.
+        if !pause.is_zero() {
             emit_line(&mut state, styled_empty());
             emit_line(
                 &mut state,
-                styled_status(&format!("sleeping {}s (type to wake)...", config.sleep_secs)),
+                styled_status(&format!(
+                    "sleeping {}s (enter to run now, or type a message and press enter)...",
+                    pause.as_secs()
+                )),
             );
 
             // Update status bar during sleep.
             if let Some(ref mut s) = state {
                 s.status = format!("{} | sleeping...", s.status);
             }
+            if let Some(ref status) = status {
+                status.update(|s| s.state = RunState::Sleeping);
+            }
 
-            tokio::select! {
-                _ = tokio::time::sleep(sleep_duration) => {}
+            let wake_at = tokio::time::Instant::now() + pause;
+            'sleep: loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(wake_at) => break 'sleep,
 
-                // TUI events during sleep.
-                Some(tui_event) = async {
-                    match tui.as_mut() {
-                        Some(t) => t.next_event().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    match tui_event {
-                        TuiEvent::Key(key) => {
-                            match (key.code, key.modifiers) {
-                                (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
-                                    break 'outer;
-                                }
-                                (KeyCode::Char('d'), m) if m.contains(KeyModifiers::CONTROL) => {
-                                    let empty = state.as_ref().is_none_or(|s| s.input_buf.is_empty());
-                                    if empty { break 'outer; }
-                                }
-                                (KeyCode::Enter, _) => {
-                                    if let Some(ref mut s) = state {
-                                        let text = s.take_input();
-                                        if !text.trim().is_empty() {
+                    // TUI events during sleep.
+                    Some(tui_event) = async {
+                        match tui.as_mut() {
+                            Some(t) => t.next_event().await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        match tui_event {
+                            TuiEvent::Key(key) => {
+                                match (key.code, key.modifiers) {
+                                    (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+                                        break 'outer;
+                                    }
+                                    (KeyCode::Char('d'), m) if m.contains(KeyModifiers::CONTROL) => {
+                                        let empty = state.as_ref().is_none_or(|s| s.input_buf.is_empty());
+                                        if empty { break 'outer; }
+                                    }
+                                    (KeyCode::Enter, _) => {
+                                        let text = state.as_mut().map(|s| s.take_input()).unwrap_or_default();
+                                        if text.trim().is_empty() {
+                                            emit_line(&mut state, styled_status("waking up early"));
+                                        } else {
                                             emit_line(&mut state, styled_status(&format!("received: {}", text)));
                                             pending_input = Some(text);
                                         }
+                                        break 'sleep;
                                     }
-                                }
-                                (KeyCode::Backspace, _) => {
-                                    if let Some(ref mut s) = state {
-                                        s.backspace();
+                                    (KeyCode::Backspace, _) => {
+                                        if let Some(ref mut s) = state {
+                                            s.backspace();
+                                        }
                                     }
-                                }
-                                (KeyCode::Char(ch), m) if !m.contains(KeyModifiers::CONTROL) => {
-                                    if let Some(ref mut s) = state {
-                                        s.push_char(ch);
+                                    (KeyCode::Char(ch), m) if !m.contains(KeyModifiers::CONTROL) => {
+                                        if let Some(ref mut s) = state {
+                                            s.push_char(ch);
+                                        }
                                     }
+                                    _ => {}
                                 }
-                                _ => {}
                             }
-                        }
-                        TuiEvent::Render => {
-                            if let (Some(t), Some(s)) = (tui.as_mut(), state.as_mut()) {
-                                t.draw(s).ok();
+                            TuiEvent::Render => {
+                                if let (Some(t), Some(s)) = (tui.as_mut(), state.as_mut()) {
+                                    t.draw(s).ok();
+                                }
                             }
+                            TuiEvent::Resize(_, _) => {}
                         }
-                        TuiEvent::Resize(_, _) => {}
                     }
-                }
 
-                // Fallback: line-buffered stdin for non-interactive mode.
-                result = async {
-                    match stdin_reader.as_mut() {
-                        Some(reader) => reader.next_line().await,
-                        None => std::future::pending().await,
-                    }
-                } => {
-                    match result {
-                        Ok(Some(input)) if !input.trim().is_empty() => {
-                            emit_line(&mut state, styled_status(&format!("received: {}", input)));
-                            pending_input = Some(input);
+                    // Fallback: line-buffered stdin for non-interactive mode.
+                    result = async {
+                        match stdin_reader.as_mut() {
+                            Some(reader) => reader.next_line().await,
+                            None => std::future::pending().await,
                         }
-                        Ok(None) => {
-                            break 'outer;
+                    } => {
+                        match result {
+                            Ok(Some(input)) if !input.trim().is_empty() => {
+                                emit_line(&mut state, styled_status(&format!("received: {}", input)));
+                                pending_input = Some(input);
+                            }
+                            Ok(None) => {
+                                break 'outer;
+                            }
+                            _ => {}
                         }
-                        _ => {}
+                        // Any line, even an empty one, ends the sleep.
+                        break 'sleep;
                     }
                 }
             }
         }
     }
 
+    if let Some(ref status) = status {
+        status.update(|s| s.state = RunState::Finished);
+    }
+
     // Restore the terminal: clears the 2-line inline viewport, disables
     // raw mode.  Output is already in terminal scrollback — no replay needed.
     if let Some(ref mut t) = tui {
@@ -900,23 +1569,43 @@ pub async fn run(
     drop(state);
 
     // Print summary to plain stderr (alternate screen already exited).
-    eprintln!("\n### Summary\n");
-    eprintln!("Result:    {}", truncate_string(&response_summary, 200));
+    plain!("\n### Summary\n");
+    plain!(
+        "Result:    {}",
+        truncate_with_ellipsis(&response_summary, 200)
+    );
     if let Some(ref wt_result) = worktree_result {
-        eprintln!("Action:    {}", wt_result);
+        plain!("Action:    {}", wt_result);
+    }
+    if let Some(ref wt) = worktree
+        && keep_worktree
+    {
+        plain!(
+            "Worktree:  {} (kept; `openbot worktrees clean` removes it)",
+            wt.path.display()
+        );
     }
-    eprintln!("Duration:  {}s", duration_secs);
+    plain!("Duration:  {}s", duration_secs);
+    let est_cost_usd = last_token_info
+        .as_ref()
+        .and_then(|info| config.estimate_cost(&default_model, &token_snapshot(info)));
     if let Some(ref info) = last_token_info {
         let u = &info.total_token_usage;
-        eprintln!(
+        plain!(
             "Tokens:    {} input ({} cached) / {} output ({} reasoning)",
-            u.input_tokens, u.cached_input_tokens, u.output_tokens, u.reasoning_output_tokens,
+            u.input_tokens,
+            u.cached_input_tokens,
+            u.output_tokens,
+            u.reasoning_output_tokens,
         );
         if let Some(ctx) = info.model_context_window {
             let pct = u.percent_of_context_window_remaining(ctx);
-            eprintln!("Context:   {}% remaining ({} window)", pct, ctx);
+            plain!("Context:   {}% remaining ({} window)", pct, ctx);
         }
     }
+    if let Some(cost) = est_cost_usd {
+        plain!("Est. cost: ${cost:.2}");
+    }
     if let Some(ref rl) = last_rate_limits {
         if let Some(ref primary) = rl.primary {
             let reset_str = match primary.resets_at {
@@ -934,20 +1623,54 @@ pub async fn run(
                 }
                 None => String::new(),
             };
-            eprintln!("Rate:      {:.0}% used{}", primary.used_percent, reset_str);
+            plain!("Rate:      {:.0}% used{}", primary.used_percent, reset_str);
         }
         if let Some(ref credits) = rl.credits {
             if credits.unlimited {
-                eprintln!("Credits:   unlimited");
+                plain!("Credits:   unlimited");
             } else if let Some(ref balance) = credits.balance {
-                eprintln!("Credits:   ${}", balance);
+                plain!("Credits:   ${}", balance);
             }
         }
         if let Some(ref plan) = rl.plan_type {
-            eprintln!("Plan:      {:?}", plan);
+            plain!("Plan:      {:?}", plan);
+        }
+    }
+    let resume_command = format!("openbot run -b {bot_name} --resume {session_id}");
+    plain!("Resume:    {resume_command}");
+
+    // Machine-readable result for `--result-file` and the `notify_url` webhook.
+    let result = json!({
+        "bot": bot_name,
+        "session_id": session_id,
+        "dry_run": dry_run,
+        "summary": response_summary,
+        "action": worktree_result,
+        "duration_secs": duration_secs,
+        "tokens": last_token_info.as_ref().map(|info| {
+            let u = &info.total_token_usage;
+            json!({
+                "input_tokens": u.input_tokens,
+                "cached_input_tokens": u.cached_input_tokens,
+                "output_tokens": u.output_tokens,
+                "reasoning_output_tokens": u.reasoning_output_tokens,
+                "context_window": info.model_context_window,
+            })
+        }),
+        "est_cost_usd": est_cost_usd,
+        "rate_limits": last_rate_limits.as_ref().map(rate_limits_json),
+        "resume_command": resume_command,
+    });
+    if let Some(ref path) = result_file {
+        if let Err(e) = write_result(path, &result) {
+            plain!("warning: failed to write result file: {e:#}");
+        }
+    }
+    if let Some(ref url) = config.notify_url {
+        if let Err(e) = notify::send(url, config.notify_body.as_deref(), &result).await {
+            plain!("warning: notification failed: {e:#}");
         }
     }
-    eprintln!("Resume:    openbot run --resume {session_id}");
 
     // Shut down codex with a timeout.
     thread.submit(Op::Shutdown).await.ok();
@@ -962,28 +1685,233 @@ pub async fn run(
     })
     .await;
 
+    if let Some(reason) = gave_up {
+        anyhow::bail!("{reason}");
+    }
+    Ok(())
+}
+
+/// Run several bots concurrently, each on its own task in its own worktree.
+///
+/// Output is plain text with a `[bot]` prefix per line. A bot that fails is
+/// reported without stopping the others; the combined result is an error if
+/// any bot failed.
+pub async fn run_many(bots: Vec<(String, BotConfig)>, opts: RunOptions) -> Result<()> {
+    let total = bots.len();
+    // Local tasks: `run` drives the TUI types and codex thread, which need not be `Send`.
+    let local = tokio::task::LocalSet::new();
+    let handles: Vec<_> = bots
+        .into_iter()
+        .map(|(name, config)| {
+            let output = RefCell::new(LabeledOutput {
+                prefix: format!("[{name}] "),
+                partial: String::new(),
+            });
+            let opts = opts.clone();
+            let task_name = name.clone();
+            let handle = local.spawn_local(LABELED_OUTPUT.scope(output, async move {
+                let result = run(&task_name, config, opts).await;
+                if let Err(ref e) = result {
+                    plain!("error: {e:#}");
+                }
+                result
+            }));
+            (name, handle)
+        })
+        .collect();
+
+    let failed = local
+        .run_until(async {
+            let mut failed = Vec::new();
+            for (name, handle) in handles {
+                match handle.await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(_)) => failed.push(name),
+                    Err(e) => {
+                        eprintln!("[{name}] error: task failed: {e}");
+                        failed.push(name);
+                    }
+                }
+            }
+            failed
+        })
+        .await;
+
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {total} bots failed: {}",
+            failed.len(),
+            failed.join(", ")
+        );
+    }
     Ok(())
 }
 
+/// Cumulative token usage from a codex token-count event.
+fn token_snapshot(info: &TokenUsageInfo) -> TokenSnapshot {
+    let u = &info.total_token_usage;
+    TokenSnapshot {
+        input_tokens: u.input_tokens,
+        cached_input_tokens: u.cached_input_tokens,
+        output_tokens: u.output_tokens,
+        reasoning_output_tokens: u.reasoning_output_tokens,
+        context_window: info.model_context_window,
+    }
+}
+
+/// Complete a session's history record with that session's own command
+/// count, token usage, and cost estimate.
+fn finish_session_record(
+    record: SessionRecord,
+    commands: &[CommandEntry],
+    tokens: Option<TokenSnapshot>,
+    config: &BotConfig,
+) -> SessionRecord {
+    let est_cost_usd = tokens
+        .as_ref()
+        .and_then(|t| config.estimate_cost(&record.model, t));
+    SessionRecord {
+        tokens,
+        command_count: Some(commands.len()),
+        est_cost_usd,
+        ..record
+    }
+}
+
+/// Consecutive codex-error sessions after which a run stops.
+const MAX_CONSECUTIVE_ERRORS: u32 = 5;
+
+/// Shortest and longest pause after a codex error.
+const ERROR_BACKOFF_MIN_SECS: u64 = 30;
+const ERROR_BACKOFF_MAX_SECS: u64 = 15 * 60;
+
+/// Pause before retrying after `errors` consecutive codex errors: the normal
+/// sleep (at least 30s), doubled for each error after the first, capped at
+/// 15 minutes.
+fn error_backoff(sleep_secs: u64, errors: u32) -> Duration {
+    let base = sleep_secs.max(ERROR_BACKOFF_MIN_SECS);
+    let factor = 1u64 << errors.saturating_sub(1).min(16);
+    Duration::from_secs(base.saturating_mul(factor).min(ERROR_BACKOFF_MAX_SECS))
+}
+
+/// Short context usage for the status bar, e.g. "12.3k/128k, 40% left".
+fn context_meter(info: &TokenUsageInfo) -> String {
+    let u = &info.last_token_usage;
+    match info.model_context_window {
+        Some(window) => format!(
+            "{}/{}, {}% left",
+            abbreviate_count(u.tokens_in_context_window()),
+            abbreviate_count(window),
+            u.percent_of_context_window_remaining(window)
+        ),
+        None => format!(
+            "{} tokens",
+            abbreviate_count(info.total_token_usage.total_tokens)
+        ),
+    }
+}
+
+/// Abbreviate a token count: 950, 12.3k, 128k, 1.5M.
+fn abbreviate_count(n: i64) -> String {
+    let (value, unit) = if n < 1_000 {
+        return n.to_string();
+    } else if n < 1_000_000 {
+        (n as f64 / 1_000.0, "k")
+    } else {
+        (n as f64 / 1_000_000.0, "M")
+    };
+    let digits = if value < 100.0 { 1 } else { 0 };
+    let text = format!("{value:.digits$}");
+    format!("{}{unit}", text.strip_suffix(".0").unwrap_or(&text))
+}
+
+/// JSON form of the last rate-limit snapshot for `--result-file`.
+fn rate_limits_json(rl: &RateLimitSnapshot) -> serde_json::Value {
+    let window = |w: &codex_protocol::protocol::RateLimitWindow| {
+        json!({
+            "used_percent": w.used_percent,
+            "window_minutes": w.window_minutes,
+            "resets_at": w.resets_at,
+        })
+    };
+    json!({
+        "primary": rl.primary.as_ref().map(window),
+        "secondary": rl.secondary.as_ref().map(window),
+        "credits": rl.credits.as_ref().map(|c| json!({
+            "has_credits": c.has_credits,
+            "unlimited": c.unlimited,
+            "balance": c.balance,
+        })),
+        "plan_type": rl.plan_type.as_ref().map(|p| format!("{p:?}")),
+    })
+}
+
+/// Write the `--result-file` JSON, to stdout when `path` is `-`.
+fn write_result(path: &Path, result: &serde_json::Value) -> Result<()> {
+    let text = serde_json::to_string_pretty(result)?;
+    if path == Path::new("-") {
+        println!("{text}");
+        Ok(())
+    } else {
+        atomic_write(path, format!("{text}\n"))
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Fail if `model` isn't in the `known` catalog, suggesting close matches.
+///
+/// An empty catalog (e.g. offline with nothing cached) skips the check.
+fn check_model(model: &str, known: &[String]) -> Result<()> {
+    if known.is_empty() || known.iter().any(|k| k == model) {
+        return Ok(());
+    }
+    let mut known: Vec<&String> = known.iter().collect();
+    known.sort();
+    known.dedup();
+    let lower = model.to_lowercase();
+    let close: Vec<&str> = known
+        .iter()
+        .filter(|k| {
+            crate::config::edit_distance(&k.to_lowercase(), &lower) <= 3
+                || k.to_lowercase().contains(&lower)
+        })
+        .map(|k| k.as_str())
+        .collect();
+    if close.is_empty() {
+        let all: Vec<&str> = known.iter().map(|k| k.as_str()).collect();
+        anyhow::bail!(
+            "unknown model '{model}'; available models: {}",
+            all.join(", ")
+        );
+    }
+    anyhow::bail!(
+        "unknown model '{model}'; did you mean: {}?",
+        close.join(", ")
+    )
+}
+
+/// Whether a bot has recorded sessions in the workspace `slug`.
+pub(crate) fn workspace_has_history(bot_name: &str, slug: &str) -> bool {
+    crate::config::bot_workspace_history_dir(bot_name, slug)
+        .is_ok_and(|dir| history::count(&dir) > 0)
+}
+
 /// Build a stable history record ID for one loop iteration within a codex session.
 fn history_session_id(base_session_id: &str, session_number: usize) -> String {
     format!("{base_session_id}-s{session_number}")
 }
 
-/// Get the current checked-out branch name for a repo, if available.
-fn current_branch_name(repo_cwd: &Path) -> Option<String> {
-    let output = std::process::Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .current_dir(repo_cwd)
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
+/// Commit message for leftover changes, built from the first line of the session summary.
+fn auto_commit_message(bot_name: &str, summary: &str) -> String {
+    let subject = summary.lines().next().unwrap_or("").trim();
+    if subject.is_empty() {
+        format!("openbot({bot_name}): uncommitted session changes")
+    } else {
+        format!(
+            "openbot({bot_name}): {}",
+            truncate_with_ellipsis(subject, 72)
+        )
     }
-
-    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if name.is_empty() { None } else { Some(name) }
 }
 
 /// Run a git command and return a human-readable error string on failure.
@@ -997,25 +1925,33 @@ fn run_git(repo_cwd: &Path, args: &[&str]) -> std::result::Result<(), String> {
     if output.status.success() {
         Ok(())
     } else {
+        // Merge and rebase report conflicts on stdout, so keep both.
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        if stderr.is_empty() {
-            Err(format!("git {:?} failed", args))
-        } else {
-            Err(stderr)
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        match (stderr.is_empty(), stdout.is_empty()) {
+            (true, true) => Err(format!("git {:?} failed", args)),
+            (false, true) => Err(stderr),
+            (true, false) => Err(stdout),
+            (false, false) => Err(format!("{stderr}\n{stdout}")),
         }
     }
 }
 
-/// Attempt a fast-forward merge of `bot_branch` into `base_branch`, then restore the previous branch.
-fn merge_into_base_branch(repo_cwd: &Path, base_branch: &str, bot_branch: &str) -> String {
-    let previous_branch = current_branch_name(repo_cwd);
-
-    let mut result = match run_git(repo_cwd, &["checkout", base_branch]) {
-        Ok(()) => match run_git(repo_cwd, &["merge", "--ff-only", bot_branch]) {
-            Ok(()) => format!("merged {bot_branch} into {base_branch}"),
-            Err(_) => format!("merge failed; branch {bot_branch} available for manual merge"),
-        },
-        Err(_) => format!("merge failed; branch {bot_branch} available for manual merge"),
+/// Land `bot_branch` on `base_branch` using `strategy`, then restore the previous branch.
+///
+/// Conflicts are aborted so neither checkout is left mid-merge or mid-rebase.
+fn merge_into_base_branch(
+    repo_cwd: &Path,
+    worktree_path: &Path,
+    base_branch: &str,
+    bot_branch: &str,
+    strategy: MergeStrategy,
+) -> String {
+    let previous_branch = git::current_branch(repo_cwd);
+
+    let mut result = match land_branch(repo_cwd, worktree_path, base_branch, bot_branch, strategy) {
+        Ok(()) => format!("merged {bot_branch} into {base_branch}"),
+        Err(reason) => format!("{reason}; branch {bot_branch} available for manual merge"),
     };
 
     if let Some(previous) = previous_branch.as_deref()
@@ -1031,6 +1967,305 @@ fn merge_into_base_branch(repo_cwd: &Path, base_branch: &str, bot_branch: &str)
     result
 }
 
+/// Why a git step in [`land_branch`] failed.
+#[derive(Debug, PartialEq, Eq)]
+enum MergeFailure {
+    /// The branches touch the same lines.
+    Conflict,
+    /// Uncommitted changes would be overwritten.
+    DirtyTree,
+    /// `--ff-only` refused because the base branch has moved.
+    NotFastForward,
+    /// Anything else; the git message says what.
+    Other,
+}
+
+impl MergeFailure {
+    /// Classify a failed merge, rebase, or checkout from git's output.
+    fn classify(output: &str) -> Self {
+        if output.contains("CONFLICT")
+            || output.contains("could not apply")
+            || output.contains("fix conflicts")
+        {
+            MergeFailure::Conflict
+        } else if output.contains("would be overwritten")
+            || output.contains("commit your changes or stash them")
+            || output.contains("unstaged changes")
+            || output.contains("uncommitted changes")
+        {
+            MergeFailure::DirtyTree
+        } else if output.contains("fast-forward") {
+            MergeFailure::NotFastForward
+        } else {
+            MergeFailure::Other
+        }
+    }
+}
+
+/// Describe a failed `step` (e.g. "merge into main") from git's `output`.
+fn merge_failure_reason(step: &str, dir: &Path, base_branch: &str, output: &str) -> String {
+    let detail = output.lines().next().unwrap_or("").trim();
+    match MergeFailure::classify(output) {
+        MergeFailure::Conflict => format!("{step} hit conflicts and was aborted"),
+        MergeFailure::DirtyTree => format!(
+            "{step} failed: uncommitted changes in {} are in the way ({detail})",
+            dir.display()
+        ),
+        MergeFailure::NotFastForward => format!(
+            "{step} failed: {base_branch} has moved, so it is not a fast-forward (merge_strategy = \"rebase\" or \"merge\" handles this)"
+        ),
+        MergeFailure::Other => format!("{step} failed: {detail}"),
+    }
+}
+
+/// Integrate `bot_branch` into `base_branch`, returning a short failure reason.
+///
+/// On conflict the merge or rebase is aborted so no checkout is left half-merged.
+fn land_branch(
+    repo_cwd: &Path,
+    worktree_path: &Path,
+    base_branch: &str,
+    bot_branch: &str,
+    strategy: MergeStrategy,
+) -> std::result::Result<(), String> {
+    // The bot branch is checked out in its worktree, so rebase it there.
+    if strategy == MergeStrategy::Rebase
+        && let Err(output) = run_git(worktree_path, &["rebase", base_branch])
+    {
+        let _ = run_git(worktree_path, &["rebase", "--abort"]);
+        return Err(merge_failure_reason(
+            &format!("rebase onto {base_branch}"),
+            worktree_path,
+            base_branch,
+            &output,
+        ));
+    }
+
+    run_git(repo_cwd, &["checkout", base_branch]).map_err(|output| {
+        merge_failure_reason(
+            &format!("checkout of {base_branch}"),
+            repo_cwd,
+            base_branch,
+            &output,
+        )
+    })?;
+
+    let step = format!("merge into {base_branch}");
+    let args: &[&str] = match strategy {
+        MergeStrategy::Merge => &["merge", "--no-edit", bot_branch],
+        MergeStrategy::FfOnly | MergeStrategy::Rebase => &["merge", "--ff-only", bot_branch],
+    };
+    if let Err(output) = run_git(repo_cwd, args) {
+        if MergeFailure::classify(&output) == MergeFailure::Conflict {
+            let _ = run_git(repo_cwd, &["merge", "--abort"]);
+        }
+        return Err(merge_failure_reason(&step, repo_cwd, base_branch, &output));
+    }
+    Ok(())
+}
+
+/// Render one page of `lines` with a footer saying where the next page starts.
+///
+/// Backward paging counts `offset` lines up from the end; forward paging
+/// counts down from the top.
+fn paginate_lines(lines: &[String], offset: usize, limit: usize, forward: bool) -> String {
+    let total = lines.len();
+    let (start, end) = if forward {
+        let start = offset.min(total);
+        (start, start.saturating_add(limit).min(total))
+    } else {
+        let end = total.saturating_sub(offset);
+        (end.saturating_sub(limit), end)
+    };
+
+    let mut out = lines[start..end].join("\n");
+    if start == end {
+        out.push_str(&format!("[no lines at offset {offset}; {total} total]"));
+        return out;
+    }
+    out.push_str(&format!("\n\n[lines {}-{} of {}]", start + 1, end, total));
+    if forward && end < total {
+        out.push_str(&format!(
+            " More content: direction='forward', offset={end}, limit={limit}"
+        ));
+    } else if !forward && start > 0 {
+        out.push_str(&format!(
+            " Earlier content: offset={}, limit={}",
+            offset + limit,
+            limit
+        ));
+    }
+    out
+}
+
+/// What the user did at the `--start-paused` prompt.
+enum StartChoice {
+    /// Pressed Enter, with the text typed first, if any.
+    Start(Option<String>),
+    /// Pressed ctrl-c, or ctrl-d on an empty prompt.
+    Quit,
+}
+
+/// Keep the TUI drawn and collect typed input until Enter or a quit key.
+async fn wait_for_start(tui: &mut Tui, state: &mut Option<AppState>) -> StartChoice {
+    while let Some(event) = tui.next_event().await {
+        match event {
+            TuiEvent::Key(key) => match (key.code, key.modifiers) {
+                (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+                    return StartChoice::Quit;
+                }
+                (KeyCode::Char('d'), m)
+                    if m.contains(KeyModifiers::CONTROL)
+                        && state.as_ref().is_none_or(|s| s.input_buf.is_empty()) =>
+                {
+                    return StartChoice::Quit;
+                }
+                (KeyCode::Enter, _) => {
+                    let text = state.as_mut().map(|s| s.take_input()).unwrap_or_default();
+                    let text = (!text.trim().is_empty()).then_some(text);
+                    return StartChoice::Start(text);
+                }
+                (KeyCode::Backspace, _) => {
+                    if let Some(s) = state {
+                        s.backspace();
+                    }
+                }
+                (KeyCode::Char(ch), m) if !m.contains(KeyModifiers::CONTROL) => {
+                    if let Some(s) = state {
+                        s.push_char(ch);
+                    }
+                }
+                _ => {}
+            },
+            TuiEvent::Render => {
+                if let Some(s) = state {
+                    tui.draw(s).ok();
+                }
+            }
+            TuiEvent::Resize(_, _) => {}
+        }
+    }
+    // The event task ended; nothing more can be typed, so just start.
+    StartChoice::Start(None)
+}
+
+/// Handle calls to the `memory_get` dynamic tool.
+///
+/// Workspace entries win; keys only in the bot's global memory are read from
+/// there and marked `(global)` in listings.
+fn handle_memory_get_tool(
+    args: &serde_json::Value,
+    memory_path: &std::path::Path,
+    global_memory_path: &std::path::Path,
+) -> String {
+    // Read from disk so writes made during this session are visible.
+    let (store, global) = match (
+        MemoryStore::load(memory_path),
+        MemoryStore::load(global_memory_path),
+    ) {
+        (Ok(s), Ok(g)) => (s, g),
+        (Err(e), _) | (_, Err(e)) => return format!("Error loading memory: {e}"),
+    };
+    match args.get("key").and_then(|v| v.as_str()) {
+        Some(key) => match store.get(key).or_else(|| global.get(key)) {
+            Some(value) => format_value(value),
+            None => format!("No memory entry for key '{key}'."),
+        },
+        None => {
+            let mut keys: Vec<String> = store.keys_by_recency().into_iter().cloned().collect();
+            keys.extend(
+                global
+                    .keys_by_recency()
+                    .into_iter()
+                    .filter(|k| store.get(k).is_none())
+                    .map(|k| format!("{k} (global)")),
+            );
+            if keys.is_empty() {
+                return "Memory is empty.".into();
+            }
+            let mut out = format!(
+                "{} keys (most recently updated first, global keys last):\n",
+                keys.len()
+            );
+            for key in keys {
+                out.push_str(&format!("- {key}\n"));
+            }
+            out
+        }
+    }
+}
+
+/// Handle calls to the `notes_append` dynamic tool.
+fn handle_notes_append_tool(args: &serde_json::Value, notes_path: &std::path::Path) -> String {
+    let text = args.get("text").and_then(|v| v.as_str()).unwrap_or("");
+    if text.trim().is_empty() {
+        return "Error: 'text' is required.".into();
+    }
+    match crate::notes::append(notes_path, text) {
+        Ok(()) => "Appended to notes.".into(),
+        Err(e) => format!("Error appending to notes: {e:#}"),
+    }
+}
+
+/// Handle calls to the `skills` dynamic tool.
+fn handle_skills_tool(
+    args: &serde_json::Value,
+    skill_dirs: &[std::path::PathBuf],
+    skill_tags: &[String],
+) -> String {
+    // Read from disk so skills written during this session are visible.
+    let mut skills = match load_skills(skill_dirs) {
+        Ok(s) => s,
+        Err(e) => return format!("Error loading skills: {e}"),
+    };
+    skills.retain(|skill| skill.has_any_tag(skill_tags));
+
+    match args
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("list")
+    {
+        "list" => {
+            if skills.is_empty() {
+                return "No skills installed.".into();
+            }
+            let mut out = format!("{} skills:\n", skills.len());
+            for skill in &skills {
+                if skill.description.is_empty() {
+                    out.push_str(&format!("- {}\n", skill.name));
+                } else {
+                    out.push_str(&format!("- {}: {}\n", skill.name, skill.description));
+                }
+            }
+            out
+        }
+        "load" => {
+            let Some(name) = args.get("name").and_then(|v| v.as_str()) else {
+                return "Error: 'name' is required for the 'load' action.".into();
+            };
+            let Some(skill) = skills.iter().find(|s| s.name == name) else {
+                return format!("No skill named '{name}'. Use action='list' to see them.");
+            };
+            let mut out = format!("# {}\n", skill.name);
+            if !skill.description.is_empty() {
+                out.push_str(&format!("{}\n", skill.description));
+            }
+            if !skill.allowed_tools.is_empty() {
+                out.push_str(&format!(
+                    "Allowed tools: {}\n",
+                    skill.allowed_tools.join(", ")
+                ));
+            }
+            out.push_str(&format!("\n{}\n", skill.body));
+            out
+        }
+        other => format!("Unknown action '{other}'. Use 'list' or 'load'."),
+    }
+}
+
+/// Maximum matches returned by the `session_history` search action.
+const MAX_SEARCH_HITS: usize = 20;
+
 /// Handle calls to the `session_history` dynamic tool.
 fn handle_session_history_tool(args: &serde_json::Value, history_dir: &std::path::Path) -> String {
     let action = args
@@ -1052,7 +2287,7 @@ fn handle_session_history_tool(args: &serde_json::Value, history_dir: &std::path
             for r in &records {
                 let date = r.started_at.format("%Y-%m-%d %H:%M");
                 let cmd_count = r.command_count.unwrap_or(0);
-                let summary = truncate_string(&r.response_summary, 80);
+                let summary = truncate_with_ellipsis(&r.response_summary, 80);
                 out.push_str(&format!(
                     "{} | {} | {}s | {} | {}\n",
                     r.session_number, date, r.duration_secs, cmd_count, summary,
@@ -1087,9 +2322,11 @@ fn handle_session_history_tool(args: &serde_json::Value, history_dir: &std::path
                 .get("section")
                 .and_then(|v| v.as_str())
                 .unwrap_or("all");
-            // offset = how many lines back from the end to start (0 = last page)
+            // backward: offset = lines back from the end (0 = last page)
+            // forward:  offset = lines down from the top (0 = first page)
             let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
             let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+            let forward = args.get("direction").and_then(|v| v.as_str()) == Some("forward");
 
             let mut lines: Vec<String> = Vec::new();
 
@@ -1138,43 +2375,103 @@ fn handle_session_history_tool(args: &serde_json::Value, history_dir: &std::path
                         lines.push(line.to_string());
                     }
                 }
+                if let Some((summary, action)) = history::completion(&events) {
+                    lines.push(String::new());
+                    lines.push(format!("## Session Complete (action: {action})"));
+                    lines.push(summary.to_string());
+                }
             }
 
-            // Paginate from the end: offset=0 shows the last `limit` lines.
-            let total = lines.len();
-            let end = total.saturating_sub(offset);
-            let start = end.saturating_sub(limit);
-            let page: Vec<&str> = lines[start..end].iter().map(|s| s.as_str()).collect();
-
-            let mut out = page.join("\n");
-            out.push_str(&format!("\n\n[lines {}-{} of {}]", start + 1, end, total));
-            if start > 0 {
+            paginate_lines(&lines, offset, limit, forward)
+        }
+        "search" => {
+            let query = args
+                .get("query")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .trim();
+            if query.is_empty() {
+                return "query is required for the 'search' action.".into();
+            }
+            let hits = match history::search(history_dir, query, MAX_SEARCH_HITS) {
+                Ok(h) => h,
+                Err(e) => return format!("Error searching history: {e}"),
+            };
+            if hits.is_empty() {
+                return format!("No matches for '{query}'.");
+            }
+            let mut out = String::new();
+            for hit in &hits {
+                out.push_str(&format!(
+                    "Session {} ({}) {}: {}\n",
+                    hit.session_number,
+                    hit.started_at.format("%Y-%m-%d %H:%M"),
+                    hit.location,
+                    hit.snippet,
+                ));
+            }
+            if hits.len() == MAX_SEARCH_HITS {
                 out.push_str(&format!(
-                    " Earlier content: offset={}, limit={}",
-                    offset + limit,
-                    limit
+                    "\nShowing the first {MAX_SEARCH_HITS} matches (newest first). Refine the query for more."
                 ));
+            } else {
+                out.push_str(&format!("\n{} matches.", hits.len()));
             }
+            out.push_str(" Use action='view' with session_number to read a session.");
             out
         }
-        _ => format!("Unknown action '{action}'. Use 'list' or 'view'."),
-    }
-}
-
-/// Return a truncated display string with an ellipsis when over max bytes.
-fn truncate_string(s: &str, max: usize) -> String {
-    if s.len() <= max {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max])
+        _ => format!("Unknown action '{action}'. Use 'list', 'view', or 'search'."),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::TempDir;
     use std::fs;
-    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn labeled_output_holds_partial_lines_until_flush() {
+        let output = RefCell::new(LabeledOutput {
+            prefix: "[a] ".into(),
+            partial: String::new(),
+        });
+        LABELED_OUTPUT.sync_scope(output, || {
+            let mut state = None;
+            emit_delta(&mut state, "hello wo");
+            emit_delta(&mut state, "rld\nnext");
+            LABELED_OUTPUT.with(|out| assert_eq!(out.borrow().partial, "next"));
+            emit_flush(&mut state);
+            LABELED_OUTPUT.with(|out| assert!(out.borrow().partial.is_empty()));
+        });
+        assert!(!is_labeled());
+    }
+
+    #[test]
+    fn transcript_records_lines_deltas_and_plain_output() {
+        let dir = TempDir::new("transcript");
+        let path = dir.join("run.log");
+        let transcript = RefCell::new(Transcript::open(&path).unwrap());
+        TRANSCRIPT.sync_scope(transcript, || {
+            let mut state = Some(AppState::new());
+            emit_line(&mut state, styled_status("starting"));
+            emit_delta(&mut state, "hello wo");
+            emit_delta(&mut state, "rld\nnext");
+            emit_flush(&mut state);
+            plain!("Result:    done");
+        });
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log
+            .lines()
+            .map(|line| line.split_once("] ").unwrap().1)
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["  [starting]", "hello world", "next", "Result:    done"]
+        );
+        assert!(log.starts_with('['));
+    }
 
     #[test]
     fn history_session_id_includes_iteration() {
@@ -1185,14 +2482,81 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn each_session_record_counts_only_its_own_commands_and_tokens() {
+        let dir = TempDir::new("runner-per-session");
+        let mut config = BotConfig::default();
+        config.pricing.insert(
+            "test".into(),
+            crate::config::ModelPrice {
+                input: 1.0,
+                output: 0.0,
+                cached_input: None,
+            },
+        );
+
+        // Mirror the loop: one writer per session, the log cleared in between.
+        let mut commands_log = Vec::new();
+        for (n, commands) in [(1, 3), (2, 1)] {
+            commands_log.clear();
+            let initial = SessionRecord {
+                session_id: history_session_id("abc", n),
+                session_number: n,
+                started_at: Utc::now(),
+                duration_secs: 0,
+                model: "test".into(),
+                prompt_summary: String::new(),
+                response_summary: String::new(),
+                action: None,
+                tokens: None,
+                command_count: Some(0),
+                est_cost_usd: None,
+            };
+            let mut writer = SessionWriter::create(&dir, &initial).expect("create");
+            for i in 0..commands {
+                let entry = CommandEntry {
+                    command: format!("cmd {n}.{i}"),
+                    exit_code: 0,
+                    duration_ms: 1,
+                };
+                writer
+                    .append_event(&SessionEvent::Command {
+                        command: entry.command.clone(),
+                        exit_code: entry.exit_code,
+                        duration_ms: entry.duration_ms,
+                    })
+                    .expect("append");
+                commands_log.push(entry);
+            }
+            let tokens = TokenSnapshot {
+                input_tokens: 1_000_000 * n as i64,
+                ..TokenSnapshot::default()
+            };
+            let record = finish_session_record(initial, &commands_log, Some(tokens), &config);
+            writer.finalize(&record).expect("finalize");
+        }
+
+        let records = history::list(&dir).expect("list");
+        assert_eq!(records.len(), 2);
+        for rec in records {
+            let events = history::load_events(&dir, &rec.session_id).expect("events");
+            let n = rec.session_number;
+            assert_eq!(
+                rec.command_count,
+                Some(history::extract_commands(&events).len()),
+                "session {n}"
+            );
+            assert_eq!(
+                rec.tokens.expect("tokens").input_tokens,
+                1_000_000 * n as i64
+            );
+            assert_eq!(rec.est_cost_usd, Some(n as f64));
+        }
+    }
+
     #[test]
     fn merge_restores_previous_branch() {
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let tmp_dir = std::env::temp_dir().join(format!("openbot-runner-test-{nanos}"));
-        fs::create_dir_all(&tmp_dir).expect("create temp test dir");
+        let tmp_dir = TempDir::new("runner-test");
 
         run_git(&tmp_dir, &["init"]).expect("git init");
         run_git(
@@ -1206,7 +2570,7 @@ mod tests {
         run_git(&tmp_dir, &["add", "README.md"]).expect("git add base");
         run_git(&tmp_dir, &["commit", "-m", "base commit"]).expect("git commit base");
 
-        let base_branch = current_branch_name(&tmp_dir).expect("base branch name");
+        let base_branch = git::current_branch(&tmp_dir).expect("base branch name");
 
         run_git(&tmp_dir, &["checkout", "-b", "dev"]).expect("create dev branch");
         run_git(&tmp_dir, &["checkout", "-b", "bot-test", &base_branch])
@@ -1218,17 +2582,235 @@ mod tests {
 
         run_git(&tmp_dir, &["checkout", "dev"]).expect("checkout dev");
 
-        let summary = merge_into_base_branch(&tmp_dir, &base_branch, "bot-test");
+        let summary = merge_into_base_branch(
+            &tmp_dir,
+            &tmp_dir,
+            &base_branch,
+            "bot-test",
+            MergeStrategy::FfOnly,
+        );
         assert!(
             summary.starts_with("merged bot-test into"),
             "unexpected merge summary: {summary}"
         );
         assert_eq!(
-            current_branch_name(&tmp_dir).as_deref(),
+            git::current_branch(&tmp_dir).as_deref(),
             Some("dev"),
             "expected previous branch to be restored"
         );
+    }
+
+    /// Create a repo with one commit and a `bot-test` branch checked out in a worktree.
+    fn init_repo_with_bot_worktree(name: &str) -> (TempDir, String) {
+        let tmp_dir = TempDir::new(&format!("runner-{name}"));
+        let repo = tmp_dir.join("repo");
+        fs::create_dir_all(&repo).expect("create temp test dir");
+
+        run_git(&repo, &["init"]).expect("git init");
+        run_git(&repo, &["config", "user.email", "openbot-test@example.com"])
+            .expect("git config email");
+        run_git(&repo, &["config", "user.name", "openbot-test"]).expect("git config name");
+        fs::write(repo.join("README.md"), "base\n").expect("write readme");
+        run_git(&repo, &["add", "README.md"]).expect("git add base");
+        run_git(&repo, &["commit", "-m", "base commit"]).expect("git commit base");
+        let base_branch = git::current_branch(&repo).expect("base branch name");
+
+        run_git(&repo, &["worktree", "add", "-b", "bot-test", "../wt"]).expect("add worktree");
+        (tmp_dir, base_branch)
+    }
+
+    fn commit_file(dir: &Path, file: &str, contents: &str) {
+        fs::write(dir.join(file), contents).expect("write file");
+        run_git(dir, &["add", file]).expect("git add");
+        run_git(dir, &["commit", "-m", file]).expect("git commit");
+    }
+
+    #[test]
+    fn rebase_strategy_lands_branch_after_base_moved() {
+        let (tmp_dir, base_branch) = init_repo_with_bot_worktree("rebase");
+        let (repo, wt) = (tmp_dir.join("repo"), tmp_dir.join("wt"));
+        commit_file(&wt, "bot.txt", "bot\n");
+        commit_file(&repo, "base.txt", "base moved\n");
+
+        let ff =
+            merge_into_base_branch(&repo, &wt, &base_branch, "bot-test", MergeStrategy::FfOnly);
+        assert!(
+            ff.contains("has moved, so it is not a fast-forward")
+                && ff.contains("branch bot-test available for manual merge"),
+            "unexpected summary: {ff}"
+        );
+
+        let summary =
+            merge_into_base_branch(&repo, &wt, &base_branch, "bot-test", MergeStrategy::Rebase);
+        assert!(
+            summary.starts_with("merged bot-test"),
+            "unexpected summary: {summary}"
+        );
+        assert!(repo.join("bot.txt").exists());
+        assert!(repo.join("base.txt").exists());
+    }
+
+    #[test]
+    fn merge_strategy_aborts_on_conflict() {
+        let (tmp_dir, base_branch) = init_repo_with_bot_worktree("conflict");
+        let (repo, wt) = (tmp_dir.join("repo"), tmp_dir.join("wt"));
+        commit_file(&wt, "README.md", "bot\n");
+        commit_file(&repo, "README.md", "base\nmoved\n");
+
+        for strategy in [MergeStrategy::Merge, MergeStrategy::Rebase] {
+            let summary = merge_into_base_branch(&repo, &wt, &base_branch, "bot-test", strategy);
+            assert!(
+                summary.contains("conflicts and was aborted")
+                    && summary.contains("branch bot-test available for manual merge"),
+                "unexpected summary: {summary}"
+            );
+        }
+        assert!(!repo.join(".git/MERGE_HEAD").exists());
+        assert_eq!(git::current_branch(&wt).as_deref(), Some("bot-test"));
+        assert_eq!(
+            fs::read_to_string(repo.join("README.md")).expect("read readme"),
+            "base\nmoved\n"
+        );
+    }
+
+    #[test]
+    fn paginate_lines_pages_both_directions() {
+        let lines: Vec<String> = (1..=5).map(|n| format!("line {n}")).collect();
+
+        let last = paginate_lines(&lines, 0, 2, false);
+        assert!(last.starts_with("line 4\nline 5"), "{last}");
+        assert!(last.ends_with("[lines 4-5 of 5] Earlier content: offset=2, limit=2"));
+
+        let first = paginate_lines(&lines, 4, 2, false);
+        assert!(first.ends_with("[lines 1-1 of 5]"), "{first}");
+
+        let top = paginate_lines(&lines, 0, 2, true);
+        assert!(top.starts_with("line 1\nline 2"), "{top}");
+        assert!(top.ends_with("More content: direction='forward', offset=2, limit=2"));
+
+        let bottom = paginate_lines(&lines, 4, 2, true);
+        assert!(bottom.ends_with("[lines 5-5 of 5]"), "{bottom}");
+
+        assert_eq!(
+            paginate_lines(&lines, 9, 2, true),
+            "[no lines at offset 9; 5 total]"
+        );
+        assert_eq!(
+            paginate_lines(&lines, 9, 2, false),
+            "[no lines at offset 9; 5 total]"
+        );
+    }
+
+    #[test]
+    fn check_model_accepts_known_and_suggests_close_matches() {
+        let known: Vec<String> = ["gpt-5-codex", "gpt-5", "o3"].map(String::from).to_vec();
+        assert!(check_model("gpt-5", &known).is_ok());
+        assert!(check_model("anything", &[]).is_ok());
+
+        let err = check_model("gpt5-codex", &known).unwrap_err().to_string();
+        assert!(err.contains("did you mean: gpt-5-codex?"), "{err}");
+        let err = check_model("claude", &known).unwrap_err().to_string();
+        assert!(
+            err.contains("available models: gpt-5, gpt-5-codex, o3"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn merge_reports_uncommitted_changes_in_base_checkout() {
+        let (tmp_dir, base_branch) = init_repo_with_bot_worktree("dirty");
+        let (repo, wt) = (tmp_dir.join("repo"), tmp_dir.join("wt"));
+        commit_file(&wt, "README.md", "bot\n");
+        fs::write(repo.join("README.md"), "local edit\n").expect("dirty the base checkout");
+
+        let summary =
+            merge_into_base_branch(&repo, &wt, &base_branch, "bot-test", MergeStrategy::FfOnly);
+        assert!(
+            summary.contains("uncommitted changes in") && summary.contains("are in the way"),
+            "unexpected summary: {summary}"
+        );
+        assert_eq!(
+            fs::read_to_string(repo.join("README.md")).expect("read readme"),
+            "local edit\n"
+        );
+    }
+
+    #[test]
+    fn merge_failure_classification() {
+        let classify = MergeFailure::classify;
+        assert_eq!(
+            classify("Auto-merging a.txt\nCONFLICT (content): Merge conflict in a.txt"),
+            MergeFailure::Conflict
+        );
+        assert_eq!(
+            classify(
+                "error: Your local changes to the following files would be overwritten by checkout:"
+            ),
+            MergeFailure::DirtyTree
+        );
+        assert_eq!(
+            classify("fatal: Not possible to fast-forward, aborting."),
+            MergeFailure::NotFastForward
+        );
+        assert_eq!(classify("fatal: bad revision"), MergeFailure::Other);
+    }
+
+    #[test]
+    fn failed_merge_still_restores_previous_branch() {
+        let (tmp_dir, base_branch) = init_repo_with_bot_worktree("restore");
+        let (repo, wt) = (tmp_dir.join("repo"), tmp_dir.join("wt"));
+        commit_file(&wt, "bot.txt", "bot\n");
+        commit_file(&repo, "base.txt", "base moved\n");
+        run_git(&repo, &["checkout", "-b", "dev"]).expect("create dev branch");
+
+        let summary =
+            merge_into_base_branch(&repo, &wt, &base_branch, "bot-test", MergeStrategy::FfOnly);
+        assert!(
+            summary.contains("not a fast-forward"),
+            "unexpected summary: {summary}"
+        );
+        assert_eq!(git::current_branch(&repo).as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn abbreviate_count_keeps_status_bar_short() {
+        assert_eq!(abbreviate_count(950), "950");
+        assert_eq!(abbreviate_count(12_345), "12.3k");
+        assert_eq!(abbreviate_count(12_000), "12k");
+        assert_eq!(abbreviate_count(128_000), "128k");
+        assert_eq!(abbreviate_count(272_000), "272k");
+        assert_eq!(abbreviate_count(1_500_000), "1.5M");
+    }
+
+    #[test]
+    fn error_backoff_doubles_from_the_sleep_and_caps() {
+        let secs = |sleep, errors| error_backoff(sleep, errors).as_secs();
+        assert_eq!(secs(0, 1), 30);
+        assert_eq!(secs(0, 2), 60);
+        assert_eq!(secs(60, 1), 60);
+        assert_eq!(secs(60, 3), 240);
+        assert_eq!(secs(30, 10), 900);
+        assert_eq!(secs(u64::MAX, 40), 900);
+    }
+
+    #[test]
+    fn skills_tool_lists_and_loads_from_disk() {
+        let dir = TempDir::new("runner-skills");
+        fs::write(
+            dir.join("deploy.md"),
+            "---\nname: deploy\ndescription: Ship a release\n---\nRun the release script.\n",
+        )
+        .expect("write skill");
+        let dirs = [dir.to_path_buf()];
+
+        let list = handle_skills_tool(&json!({"action": "list"}), &dirs, &[]);
+        assert!(list.contains("- deploy: Ship a release"), "{list}");
+
+        let loaded = handle_skills_tool(&json!({"action": "load", "name": "deploy"}), &dirs, &[]);
+        assert!(loaded.starts_with("# deploy\nShip a release\n"), "{loaded}");
+        assert!(loaded.contains("Run the release script."), "{loaded}");
 
-        fs::remove_dir_all(&tmp_dir).ok();
+        let missing = handle_skills_tool(&json!({"action": "load", "name": "nope"}), &dirs, &[]);
+        assert!(missing.starts_with("No skill named 'nope'"), "{missing}");
     }
 }
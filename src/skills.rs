@@ -1,11 +1,16 @@
 //! Skill loading and formatting utilities.
 //!
 //! Skills are markdown documents optionally prefixed with lightweight YAML-like
-//! frontmatter (`name`, `description`).
+//! frontmatter (`name`, `description`, `tags`, `allowed-tools`).
 
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+use crate::util::truncate_with_ellipsis;
 
 /// A skill loaded from a markdown file.
 #[derive(Debug, Clone)]
@@ -18,14 +23,34 @@ pub struct Skill {
     pub body: String,
     /// Registry source repo (e.g. "obra/superpowers"), if installed from registry.
     pub source: Option<String>,
+    /// Lowercased tags for grouping and filtering.
+    pub tags: Vec<String>,
+    /// Tools the skill expects to use (`allowed-tools` in SKILL.md files).
+    pub allowed_tools: Vec<String>,
+    /// Problems found while parsing, e.g. missing frontmatter or an empty body.
+    pub warnings: Vec<String>,
+}
+
+impl Skill {
+    /// Whether this skill carries at least one of `tags` (case-insensitive).
+    /// An empty `tags` matches every skill.
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        tags.is_empty()
+            || tags
+                .iter()
+                .any(|tag| self.tags.contains(&tag.trim().to_lowercase()))
+    }
 }
 
 /// Load all markdown skills from the given directories.
 ///
 /// Non-markdown files are ignored. Individual invalid skill files are skipped
 /// with a warning so one bad file does not block startup.
+///
+/// Skills are unique by name: a skill in a later directory (bot-local) replaces
+/// one with the same name from an earlier directory (global).
 pub fn load_skills(dirs: &[impl AsRef<Path>]) -> Result<Vec<Skill>> {
-    let mut skills = Vec::new();
+    let mut skills: Vec<Skill> = Vec::new();
 
     for dir in dirs {
         let dir = dir.as_ref();
@@ -40,8 +65,19 @@ pub fn load_skills(dirs: &[impl AsRef<Path>]) -> Result<Vec<Skill>> {
             let entry = entry?;
             let path = entry.path();
             if path.extension().is_some_and(|ext| ext == "md") {
-                match parse_skill_file(&path) {
-                    Ok(skill) => skills.push(skill),
+                match load_skill_cached(&path) {
+                    Ok(skill) => {
+                        if let Some(existing) = skills.iter_mut().find(|s| s.name == skill.name) {
+                            tracing::info!(
+                                "skill '{}' from {} overrides an earlier one",
+                                skill.name,
+                                path.display()
+                            );
+                            *existing = skill;
+                        } else {
+                            skills.push(skill);
+                        }
+                    }
                     Err(e) => {
                         tracing::warn!("skipping skill file {}: {e}", path.display());
                     }
@@ -53,18 +89,110 @@ pub fn load_skills(dirs: &[impl AsRef<Path>]) -> Result<Vec<Skill>> {
     Ok(skills)
 }
 
+/// An installed skill matching a local search, with the text that matched.
+pub struct SkillMatch<'a> {
+    pub skill: &'a Skill,
+    /// The description when the name or description matched, otherwise the
+    /// first matching body line.
+    pub snippet: String,
+}
+
+/// Find skills whose name, description, or body contains `query`
+/// (case-insensitive).
+pub fn search_installed<'a>(skills: &'a [Skill], query: &str) -> Vec<SkillMatch<'a>> {
+    const SNIPPET_BYTES: usize = 120;
+
+    let needle = query.to_lowercase();
+    skills
+        .iter()
+        .filter_map(|skill| {
+            let snippet = if skill.name.to_lowercase().contains(&needle)
+                || skill.description.to_lowercase().contains(&needle)
+            {
+                skill.description.clone()
+            } else {
+                let line = skill
+                    .body
+                    .lines()
+                    .find(|line| line.to_lowercase().contains(&needle))?;
+                truncate_with_ellipsis(line.trim(), SNIPPET_BYTES)
+            };
+            Some(SkillMatch { skill, snippet })
+        })
+        .collect()
+}
+
+/// A parsed skill and the file state it was parsed from.
+struct CachedSkill {
+    modified: Option<SystemTime>,
+    len: u64,
+    skill: Skill,
+}
+
+/// Parsed skills by path. The runner reloads skills every session so new
+/// ones show up; unchanged files are served from here instead of re-read.
+static SKILL_CACHE: LazyLock<Mutex<HashMap<PathBuf, CachedSkill>>> =
+    LazyLock::new(Default::default);
+
+/// Parse a skill file, reusing the cached parse while its mtime and size are
+/// unchanged.
+fn load_skill_cached(path: &Path) -> Result<Skill> {
+    let meta = std::fs::metadata(path).with_context(|| format!("reading {}", path.display()))?;
+    let (modified, len) = (meta.modified().ok(), meta.len());
+    if let Some(cached) = SKILL_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(path)
+        && cached.modified.is_some()
+        && cached.modified == modified
+        && cached.len == len
+    {
+        return Ok(cached.skill.clone());
+    }
+
+    let skill = parse_skill_file(path)?;
+    SKILL_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            path.to_path_buf(),
+            CachedSkill {
+                modified,
+                len,
+                skill: skill.clone(),
+            },
+        );
+    Ok(skill)
+}
+
 /// Parse a single markdown skill file.
 fn parse_skill_file(path: &Path) -> Result<Skill> {
     let contents =
         std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
 
-    let fm = parse_frontmatter(&contents, path)?;
+    let skill = parse_skill(&contents, path)?;
+    for warning in &skill.warnings {
+        tracing::warn!("skill file {}: {warning}", path.display());
+    }
+    Ok(skill)
+}
+
+/// Parse skill markdown. `path` only supplies the fallback name when the
+/// frontmatter has none.
+pub fn parse_skill(contents: &str, path: &Path) -> Result<Skill> {
+    let mut fm = parse_frontmatter(contents, path)?;
+    if fm.body.trim().is_empty() {
+        fm.warnings.push("empty body".to_string());
+    }
 
     Ok(Skill {
         name: fm.name,
         description: fm.description,
         body: fm.body,
         source: fm.source,
+        tags: fm.tags,
+        allowed_tools: fm.allowed_tools,
+        warnings: fm.warnings,
     })
 }
 
@@ -74,6 +202,16 @@ struct SkillFrontmatter {
     description: String,
     body: String,
     source: Option<String>,
+    tags: Vec<String>,
+    allowed_tools: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Frontmatter keys whose value may continue as a `- item` list.
+#[derive(Clone, Copy)]
+enum ListKey {
+    Tags,
+    AllowedTools,
 }
 
 /// Parse optional frontmatter from markdown content.
@@ -85,10 +223,15 @@ struct SkillFrontmatter {
 /// description: What this skill does
 /// source: obra/superpowers
 /// installed_at: 2026-02-24T05:00:00Z
+/// tags: [testing, rust]
+/// allowed-tools: Bash, Read
 /// ---
 /// Body content here
 /// ```
 ///
+/// `tags` and `allowed-tools` (or `allowed_tools`) may also be written as
+/// `[a, b]` or as a `- item` list on the following lines.
+///
 /// If frontmatter is missing or malformed, this falls back to filename-based
 /// naming and treats the full file as body.
 fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter> {
@@ -106,6 +249,9 @@ fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter> {
             description: String::new(),
             body: content.to_string(),
             source: None,
+            tags: Vec::new(),
+            allowed_tools: Vec::new(),
+            warnings: vec!["no frontmatter; named after the file, with no description".into()],
         });
     };
 
@@ -115,6 +261,11 @@ fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter> {
             description: String::new(),
             body: content.to_string(),
             source: None,
+            tags: Vec::new(),
+            allowed_tools: Vec::new(),
+            warnings: vec![
+                "unterminated frontmatter (no closing `---`); the whole file is the body".into(),
+            ],
         });
     };
 
@@ -125,10 +276,40 @@ fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter> {
     let mut name = None;
     let mut description = None;
     let mut source = None;
+    let mut tags = Vec::new();
+    let mut allowed_tools = Vec::new();
+    let mut open_list = None;
+
+    let mut push_items = |key, value: &str| {
+        let items = parse_list(value);
+        match key {
+            ListKey::Tags => tags.extend(items.into_iter().map(|t| t.to_lowercase())),
+            ListKey::AllowedTools => allowed_tools.extend(items),
+        }
+    };
 
     for line in frontmatter.lines() {
         let line = line.trim();
-        if let Some(value) = line.strip_prefix("name:") {
+        if let Some(key) = open_list
+            && let Some(item) = line.strip_prefix("- ")
+        {
+            push_items(key, item);
+            continue;
+        }
+        open_list = None;
+        let list_value = [
+            ("tags:", ListKey::Tags),
+            ("allowed-tools:", ListKey::AllowedTools),
+            ("allowed_tools:", ListKey::AllowedTools),
+        ]
+        .into_iter()
+        .find_map(|(prefix, key)| line.strip_prefix(prefix).map(|value| (key, value)));
+        if let Some((key, value)) = list_value {
+            push_items(key, value);
+            if value.trim().is_empty() {
+                open_list = Some(key);
+            }
+        } else if let Some(value) = line.strip_prefix("name:") {
             name = Some(value.trim().to_string());
         } else if let Some(value) = line.strip_prefix("description:") {
             description = Some(value.trim().to_string());
@@ -142,9 +323,30 @@ fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter> {
         description: description.unwrap_or_default(),
         body,
         source,
+        tags,
+        allowed_tools,
+        warnings: Vec::new(),
     })
 }
 
+/// Split an inline list value (`a, b` or `[a, "b"]`) into items.
+fn parse_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Install / remove skills
 // ---------------------------------------------------------------------------
@@ -211,6 +413,12 @@ pub fn format_skills_section(skills: &[Skill]) -> String {
         if !skill.description.is_empty() {
             out.push_str(&format!("{}\n", skill.description));
         }
+        if !skill.allowed_tools.is_empty() {
+            out.push_str(&format!(
+                "Allowed tools: {}\n",
+                skill.allowed_tools.join(", ")
+            ));
+        }
         if !skill.body.is_empty() {
             out.push_str(&format!("\n{}\n", skill.body));
         }
@@ -218,3 +426,191 @@ pub fn format_skills_section(skills: &[Skill]) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    #[test]
+    fn search_installed_matches_name_description_and_body() {
+        let skill = |name: &str, description: &str, body: &str| Skill {
+            name: name.into(),
+            description: description.into(),
+            body: body.into(),
+            source: None,
+            tags: Vec::new(),
+            allowed_tools: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let skills = [
+            skill(
+                "tdd",
+                "Write the failing test first",
+                "Red, green, refactor.",
+            ),
+            skill(
+                "release",
+                "Cut a release",
+                "Bump the version.\n  Run cargo TEST --all.\n",
+            ),
+            skill("docs", "Keep docs current", "Update the README."),
+        ];
+
+        let hits = search_installed(&skills, "test");
+        let found: Vec<(&str, &str)> = hits
+            .iter()
+            .map(|m| (m.skill.name.as_str(), m.snippet.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("tdd", "Write the failing test first"),
+                ("release", "Run cargo TEST --all."),
+            ]
+        );
+        assert!(search_installed(&skills, "deploy").is_empty());
+    }
+
+    #[test]
+    fn tags_parse_inline_and_block_lists() {
+        let path = Path::new("skill.md");
+        let inline = "---\nname: a\ntags: [Testing, \"rust\"]\n---\nBody";
+        assert_eq!(
+            parse_frontmatter(inline, path).expect("parse").tags,
+            ["testing", "rust"]
+        );
+
+        let bare = "---\ntags: ci, release\ndescription: d\n---\nBody";
+        assert_eq!(
+            parse_frontmatter(bare, path).expect("parse").tags,
+            ["ci", "release"]
+        );
+
+        let block = "---\ntags:\n  - docs\n  - Writing\ndescription: d\n---\n- not a tag";
+        let fm = parse_frontmatter(block, path).expect("parse");
+        assert_eq!(fm.tags, ["docs", "writing"]);
+        assert_eq!(fm.description, "d");
+
+        let skill = Skill {
+            name: "a".into(),
+            description: String::new(),
+            body: String::new(),
+            source: None,
+            tags: fm.tags,
+            allowed_tools: Vec::new(),
+            warnings: Vec::new(),
+        };
+        assert!(skill.has_any_tag(&[]));
+        assert!(skill.has_any_tag(&["ci".into(), "Docs".into()]));
+        assert!(!skill.has_any_tag(&["ci".into()]));
+    }
+
+    #[test]
+    fn allowed_tools_parse_and_appear_in_the_prompt() {
+        let path = Path::new("skill.md");
+        let inline = "---\nname: pdf\nallowed-tools: Bash, Read\n---\nBody";
+        let fm = parse_frontmatter(inline, path).expect("parse");
+        assert_eq!(fm.allowed_tools, ["Bash", "Read"]);
+
+        let block =
+            "---\nname: pdf\nallowed_tools:\n  - Bash(git:*)\n  - Write\ntags: docs\n---\nBody";
+        let fm = parse_frontmatter(block, path).expect("parse");
+        assert_eq!(fm.allowed_tools, ["Bash(git:*)", "Write"]);
+        assert_eq!(fm.tags, ["docs"]);
+
+        let skill = Skill {
+            name: fm.name,
+            description: fm.description,
+            body: fm.body,
+            source: None,
+            tags: fm.tags,
+            allowed_tools: fm.allowed_tools,
+            warnings: Vec::new(),
+        };
+        let section = format_skills_section(&[skill]);
+        assert!(
+            section.contains("### pdf\nAllowed tools: Bash(git:*), Write\n"),
+            "{section}"
+        );
+    }
+
+    #[test]
+    fn bot_local_skill_overrides_global_skill_with_the_same_name() {
+        let root = TempDir::new("skills-override");
+        let (global, local) = (root.join("global"), root.join("local"));
+        std::fs::create_dir_all(&global).expect("create global");
+        std::fs::create_dir_all(&local).expect("create local");
+        std::fs::write(
+            global.join("review.md"),
+            "---\nname: review\ndescription: Global review\n---\nGlobal",
+        )
+        .expect("write global");
+        std::fs::write(global.join("deploy.md"), "Deploy steps").expect("write global");
+        std::fs::write(
+            local.join("code-review.md"),
+            "---\nname: review\ndescription: Bot review\n---\nLocal",
+        )
+        .expect("write local");
+
+        let skills = load_skills(&[&global, &local]).expect("load");
+        assert_eq!(skills.len(), 2);
+        let review: Vec<&Skill> = skills.iter().filter(|s| s.name == "review").collect();
+        assert_eq!(review.len(), 1);
+        assert_eq!(review[0].description, "Bot review");
+        assert_eq!(review[0].body, "Local");
+    }
+
+    #[test]
+    fn frontmatter_problems_become_warnings() {
+        let path = Path::new("notes.md");
+        let warnings = |content: &str| parse_frontmatter(content, path).expect("parse").warnings;
+        assert!(warnings("---\nname: a\ndescription: d\n---\nBody").is_empty());
+        assert!(warnings("Just a body")[0].starts_with("no frontmatter"));
+        assert!(warnings("---\nname: a\nBody without a close")[0].starts_with("unterminated"));
+
+        let dir = TempDir::new("skill-empty");
+        let file = dir.join("empty.md");
+        std::fs::write(&file, "---\nname: empty\n---\n\n").expect("write");
+        let skill = parse_skill_file(&file).expect("lenient parse");
+        assert_eq!(skill.name, "empty");
+        assert_eq!(skill.warnings, ["empty body"]);
+    }
+
+    #[test]
+    fn reloading_reuses_unchanged_skills_and_sees_edits() {
+        let dir = TempDir::new("skills-cache");
+        for i in 0..10 {
+            std::fs::write(
+                dir.join(format!("skill-{i}.md")),
+                format!("---\nname: skill-{i}\ndescription: Skill {i}\n---\nStep one.\n"),
+            )
+            .expect("write skill");
+        }
+        assert_eq!(load_skills(&[&dir]).expect("cold load").len(), 10);
+
+        // Mark a cached entry; an unchanged file must come back from the cache.
+        SKILL_CACHE
+            .lock()
+            .unwrap()
+            .get_mut(&dir.join("skill-3.md"))
+            .expect("cached after the first load")
+            .skill
+            .description = "from cache".into();
+        let skills = load_skills(&[&dir]).expect("warm load");
+        let cached = skills.iter().find(|s| s.name == "skill-3").expect("cached");
+        assert_eq!(cached.description, "from cache");
+
+        std::fs::write(
+            dir.join("skill-7.md"),
+            "---\nname: skill-7\ndescription: Rewritten\n---\nNew body",
+        )
+        .expect("rewrite skill");
+        std::fs::write(dir.join("brand-new.md"), "Fresh skill").expect("add skill");
+        let skills = load_skills(&[&dir]).expect("reload");
+        assert_eq!(skills.len(), 11);
+        let edited = skills.iter().find(|s| s.name == "skill-7").expect("edited");
+        assert_eq!(edited.description, "Rewritten");
+        assert!(skills.iter().any(|s| s.name == "brand-new"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Heartbeat file for external monitoring (`openbot run --status-file`).
+//!
+//! The file is rewritten atomically on every state change and at least every
+//! [`HEARTBEAT_INTERVAL`], so a watchdog can tell a stalled bot (old
+//! `last_event_at`) from a dead one (old `updated_at`).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::history::TokenSnapshot;
+
+/// How often the file is rewritten even when nothing changed.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the bot is doing right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    /// Setting up the worktree and codex session.
+    Starting,
+    /// A session is in progress.
+    Running,
+    /// Waiting between sessions.
+    Sleeping,
+    /// The run ended.
+    Finished,
+}
+
+/// The JSON object written to the status file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub bot: String,
+    pub pid: u32,
+    pub state: RunState,
+    /// Current session number in the workspace (0 before the first starts).
+    pub session: usize,
+    /// Worktree branch, if the run uses one.
+    pub branch: Option<String>,
+    /// When codex last sent an event.
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Token usage reported so far.
+    pub tokens: Option<TokenSnapshot>,
+    /// When this file was last written.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Keeps the status file current for the lifetime of a run.
+pub struct StatusFile {
+    path: PathBuf,
+    status: Arc<Mutex<Status>>,
+    heartbeat: tokio::task::JoinHandle<()>,
+}
+
+impl StatusFile {
+    /// Write the initial status and start the heartbeat.
+    pub fn start(path: PathBuf, bot: &str, branch: Option<String>) -> Self {
+        let status = Arc::new(Mutex::new(Status {
+            bot: bot.to_string(),
+            pid: std::process::id(),
+            state: RunState::Starting,
+            session: 0,
+            branch,
+            last_event_at: None,
+            tokens: None,
+            updated_at: Utc::now(),
+        }));
+        write(&path, &status);
+
+        let heartbeat = tokio::spawn({
+            let path = path.clone();
+            let status = status.clone();
+            async move {
+                let mut tick = tokio::time::interval(HEARTBEAT_INTERVAL);
+                tick.tick().await;
+                loop {
+                    tick.tick().await;
+                    write(&path, &status);
+                }
+            }
+        });
+        Self {
+            path,
+            status,
+            heartbeat,
+        }
+    }
+
+    /// Apply `change` and write the file right away.
+    pub fn update(&self, change: impl FnOnce(&mut Status)) {
+        if let Ok(mut status) = self.status.lock() {
+            change(&mut status);
+        }
+        write(&self.path, &self.status);
+    }
+
+    /// Note that codex sent an event; the next heartbeat writes it.
+    pub fn touch(&self) {
+        if let Ok(mut status) = self.status.lock() {
+            status.last_event_at = Some(Utc::now());
+        }
+    }
+}
+
+impl Drop for StatusFile {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+    }
+}
+
+/// Stamp `updated_at` and write the status, warning on failure.
+fn write(path: &std::path::Path, status: &Mutex<Status>) {
+    let json = match status.lock() {
+        Ok(mut status) => {
+            status.updated_at = Utc::now();
+            serde_json::to_string_pretty(&*status)
+        }
+        Err(_) => return,
+    };
+    let result = json
+        .map_err(anyhow::Error::from)
+        .and_then(|json| crate::util::atomic_write(path, json + "\n"));
+    if let Err(e) = result {
+        warn!("failed to write status file {}: {e:#}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+
+    #[tokio::test]
+    async fn status_file_tracks_state_changes() {
+        let dir = TempDir::new("status");
+        let path = dir.join("status.json");
+        let read = || -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read status"))
+                .expect("parse status")
+        };
+
+        let status = StatusFile::start(path.clone(), "mybot", Some("openbot/mybot-1".into()));
+        assert_eq!(read()["state"], "starting");
+        assert_eq!(read()["branch"], "openbot/mybot-1");
+
+        status.update(|s| {
+            s.state = RunState::Running;
+            s.session = 4;
+        });
+        status.touch();
+        let first = read();
+        assert_eq!(first["state"], "running");
+        assert_eq!(first["session"], 4);
+        assert!(first["last_event_at"].is_null());
+
+        status.update(|s| s.state = RunState::Finished);
+        let last = read();
+        assert_eq!(last["state"], "finished");
+        assert!(last["last_event_at"].is_string());
+
+        drop(status);
+    }
+}
@@ -47,6 +47,10 @@ pub struct AppState {
     pending_lines: Vec<Line<'static>>,
     /// Delta accumulator for streaming text (partial line).
     partial_line: String,
+    /// Whether streamed agent text is currently inside a ``` fenced block.
+    in_code_block: bool,
+    /// Whether command stdout/stderr is streamed (toggled with ctrl-o).
+    pub show_command_output: bool,
 }
 
 impl AppState {
@@ -56,6 +60,8 @@ impl AppState {
             status: String::new(),
             pending_lines: Vec::new(),
             partial_line: String::new(),
+            in_code_block: false,
+            show_command_output: false,
         }
     }
 
@@ -65,12 +71,13 @@ impl AppState {
     }
 
     /// Accumulate streaming delta text.  Completed lines (split on `\n`) are
-    /// flushed with the `"· "` agent prefix.
+    /// flushed with the `"· "` agent prefix, or with code styling while
+    /// inside a fenced code block.
     pub fn append_delta(&mut self, text: &str) {
         for ch in text.chars() {
             if ch == '\n' {
                 let finished = std::mem::take(&mut self.partial_line);
-                self.pending_lines.push(styled_agent(&finished));
+                self.push_agent_line(&finished);
             } else {
                 self.partial_line.push(ch);
             }
@@ -78,11 +85,28 @@ impl AppState {
     }
 
     /// Flush any remaining partial line (e.g. at end of agent turn).
+    ///
+    /// Also resets the code fence state so an unterminated block doesn't
+    /// bleed into later output.
     pub fn flush_partial(&mut self) {
         if !self.partial_line.is_empty() {
             let finished = std::mem::take(&mut self.partial_line);
-            self.pending_lines.push(styled_agent(&finished));
+            self.push_agent_line(&finished);
         }
+        self.in_code_block = false;
+    }
+
+    /// Style one completed agent line, toggling fence state on ``` lines.
+    fn push_agent_line(&mut self, text: &str) {
+        let line = if text.trim_start().starts_with("```") {
+            self.in_code_block = !self.in_code_block;
+            styled_code_fence(text)
+        } else if self.in_code_block {
+            styled_code(text)
+        } else {
+            styled_agent(text)
+        };
+        self.pending_lines.push(line);
     }
 
     /// Drain pending lines for `insert_before`.
@@ -100,6 +124,16 @@ impl AppState {
         self.input_buf.pop();
     }
 
+    /// Flip `show_command_output`, returning a status message.
+    pub fn toggle_command_output(&mut self) -> &'static str {
+        self.show_command_output = !self.show_command_output;
+        if self.show_command_output {
+            "command output on (ctrl-o to hide)"
+        } else {
+            "command output off (ctrl-o to show)"
+        }
+    }
+
     /// Take the input buffer contents, clearing it.
     pub fn take_input(&mut self) -> String {
         std::mem::take(&mut self.input_buf)
@@ -283,7 +317,7 @@ fn footer(frame: &mut ratatui::Frame, state: &AppState) {
 
 // ── Styled line constructors ────────────────────────────────────────────
 
-/// Bold text for session headers (e.g. "## Session 5").
+/// Bold text for section headers (e.g. "### Output").
 pub fn styled_header(text: &str) -> Line<'static> {
     Line::from(Span::styled(
         text.to_string(),
@@ -291,6 +325,19 @@ pub fn styled_header(text: &str) -> Line<'static> {
     ))
 }
 
+/// Session separator: "──── Session 5 · 14:05 ────" with a dim rule.
+pub fn styled_rule(label: &str) -> Line<'static> {
+    let rule = Style::default().fg(Color::DarkGray);
+    Line::from(vec![
+        Span::styled("──── ", rule),
+        Span::styled(
+            label.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" ────", rule),
+    ])
+}
+
 /// Agent output: dim "· " prefix + text.
 pub fn styled_agent(text: &str) -> Line<'static> {
     Line::from(vec![
@@ -299,6 +346,22 @@ pub fn styled_agent(text: &str) -> Line<'static> {
     ])
 }
 
+/// Code fence delimiter inside agent output (e.g. "```rust"), dimmed.
+pub fn styled_code_fence(text: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        format!("  {text}"),
+        Style::default().fg(Color::DarkGray),
+    ))
+}
+
+/// Line inside a fenced code block: indented, no agent prefix, yellow.
+pub fn styled_code(text: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        format!("  {text}"),
+        Style::default().fg(Color::Yellow),
+    ))
+}
+
 /// Shell command: dim cyan "  $ " prefix + command text.
 pub fn styled_command(cmd: &str) -> Line<'static> {
     Line::from(vec![
@@ -338,6 +401,17 @@ pub fn styled_worked(duration: std::time::Duration) -> Line<'static> {
     ))
 }
 
+/// Model reasoning: dim italic text behind a "  ⋮ " gutter.
+pub fn styled_reasoning(text: &str) -> Line<'static> {
+    let style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::ITALIC);
+    Line::from(vec![
+        Span::styled("  ⋮ ", Style::default().fg(Color::DarkGray)),
+        Span::styled(text.to_string(), style),
+    ])
+}
+
 /// Dim status text like "[steered: ...]", "[queued: ...]", "[interrupting...]".
 pub fn styled_status(text: &str) -> Line<'static> {
     Line::from(Span::styled(
@@ -385,3 +459,108 @@ pub fn line_to_plain(line: &Line<'_>) -> String {
     }
     out
 }
+
+// ── Progress spinner ────────────────────────────────────────────────────
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// One spinner frame: glyph, label, and elapsed seconds.
+fn spinner_line(label: &str, tick: usize, elapsed: std::time::Duration) -> String {
+    let glyph = SPINNER_FRAMES[tick % SPINNER_FRAMES.len()];
+    format!("{glyph} {label} ({:.1}s)", elapsed.as_secs_f64())
+}
+
+/// Await `fut` while showing a spinner and elapsed time on stderr, clearing
+/// the line when it completes. Without a TTY on stderr this just awaits.
+pub async fn with_spinner<F: std::future::Future>(label: &str, fut: F) -> F::Output {
+    use std::io::{IsTerminal, Write};
+
+    if !io::stderr().is_terminal() {
+        return fut.await;
+    }
+    let start = std::time::Instant::now();
+    let mut tick = tokio::time::interval(std::time::Duration::from_millis(100));
+    tokio::pin!(fut);
+    let mut frame = 0;
+    let out = loop {
+        tokio::select! {
+            out = &mut fut => break out,
+            _ = tick.tick() => {
+                let mut stderr = io::stderr();
+                let _ = write!(stderr, "\r{}", spinner_line(label, frame, start.elapsed()));
+                let _ = stderr.flush();
+                frame += 1;
+            }
+        }
+    };
+    let mut stderr = io::stderr();
+    let _ = stderr.execute(crossterm::terminal::Clear(
+        crossterm::terminal::ClearType::CurrentLine,
+    ));
+    let _ = write!(stderr, "\r");
+    let _ = stderr.flush();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_fence_spans_deltas_and_resets_on_flush() {
+        let mut state = AppState::new();
+        state.append_delta("intro\n```rust\nlet x");
+        state.append_delta(" = 1;\n```\nafter\n```\nunclosed");
+        state.flush_partial();
+        let lines: Vec<String> = state.take_pending().iter().map(line_to_plain).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "· intro",
+                "  ```rust",
+                "  let x = 1;",
+                "  ```",
+                "· after",
+                "  ```",
+                "  unclosed",
+            ]
+        );
+        assert!(!state.in_code_block);
+
+        state.append_delta("next turn\n");
+        assert_eq!(line_to_plain(&state.take_pending()[0]), "· next turn");
+    }
+
+    #[test]
+    fn spinner_line_cycles_frames_and_shows_elapsed() {
+        let elapsed = std::time::Duration::from_millis(1250);
+        assert_eq!(spinner_line("Searching", 0, elapsed), "⠋ Searching (1.2s)");
+        assert_eq!(spinner_line("Searching", 11, elapsed), "⠙ Searching (1.2s)");
+    }
+
+    #[test]
+    fn command_output_is_hidden_until_toggled() {
+        let mut state = AppState::new();
+        assert!(!state.show_command_output);
+        assert!(
+            state
+                .toggle_command_output()
+                .starts_with("command output on")
+        );
+        assert!(state.show_command_output);
+        assert!(
+            state
+                .toggle_command_output()
+                .starts_with("command output off")
+        );
+        assert!(!state.show_command_output);
+    }
+
+    #[test]
+    fn styled_rule_frames_the_label() {
+        assert_eq!(
+            line_to_plain(&styled_rule("Session 3 · 14:05")),
+            "──── Session 3 · 14:05 ────"
+        );
+    }
+}
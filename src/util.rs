@@ -0,0 +1,206 @@
+//! Small string and filesystem helpers shared across modules.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Return the longest prefix of `s` that fits in `max` bytes without
+/// splitting a UTF-8 character.
+pub fn truncate(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let end = (0..=max)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0);
+    &s[..end]
+}
+
+/// Like [`truncate`], but appends `...` when anything was cut off.
+pub fn truncate_with_ellipsis(s: &str, max: usize) -> String {
+    let cut = truncate(s, max);
+    if cut.len() == s.len() {
+        s.to_string()
+    } else {
+        format!("{cut}...")
+    }
+}
+
+/// Write `contents` to `path` so readers only ever see the old or new file.
+///
+/// Data goes to a temp file in the same directory, is synced, then renamed
+/// over the target. The temp file is removed if any step fails.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("invalid path {}", path.display()))?
+        .to_string_lossy();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let tmp_path = dir.join(format!(".{file_name}.{}.{nanos}.tmp", std::process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)
+            .with_context(|| format!("creating {}", tmp_path.display()))?;
+        file.write_all(contents.as_ref())
+            .with_context(|| format!("writing {}", tmp_path.display()))?;
+        file.sync_all()
+            .with_context(|| format!("syncing {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))
+    })();
+
+    if result.is_err() {
+        std::fs::remove_file(&tmp_path).ok();
+    }
+    result
+}
+
+/// The user's editor command: `$VISUAL`, then `$EDITOR`, then the platform default.
+///
+/// Returned as program plus arguments so values like `code --wait` work.
+pub fn editor_command() -> Vec<String> {
+    let configured = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|cmd| {
+            cmd.split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .find(|parts| !parts.is_empty());
+    configured.unwrap_or_else(|| {
+        let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+        vec![fallback.to_string()]
+    })
+}
+
+/// Open `path` in the user's editor and wait for it to exit.
+pub fn open_in_editor(path: &Path) -> Result<()> {
+    let command = editor_command();
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .arg(path)
+        .status()
+        .with_context(|| format!("launching editor '{}'", command.join(" ")))?;
+    if !status.success() {
+        anyhow::bail!("editor '{}' exited with {status}", command.join(" "));
+    }
+    Ok(())
+}
+
+/// A scratch directory for tests, removed on drop even when an assertion
+/// fails first. Derefs to its path.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Create an empty `openbot-<name>-…` directory under the system temp dir.
+    pub(crate) fn new(name: &str) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir =
+            std::env::temp_dir().join(format!("openbot-{name}-{}-{nanos}-{n}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        Self(dir)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .expect("read dir")
+            .map(|e| e.expect("entry").file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn truncate_respects_char_boundaries() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 3), "hel");
+        // "é" is two bytes; cutting inside it backs off to the previous char.
+        assert_eq!(truncate("café au lait", 4), "caf");
+        assert_eq!(truncate("café au lait", 5), "café");
+        // "🤖" is four bytes.
+        assert_eq!(truncate("🤖🤖", 6), "🤖");
+        assert_eq!(truncate("🤖", 3), "");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn truncate_with_ellipsis_marks_cuts_only() {
+        assert_eq!(truncate_with_ellipsis("short", 80), "short");
+        assert_eq!(truncate_with_ellipsis("naïve résumé", 3), "na...");
+        assert_eq!(truncate_with_ellipsis("done 🎉 ok", 7), "done ...");
+    }
+
+    #[test]
+    fn atomic_write_replaces_target_without_leftovers() {
+        let dir = TempDir::new("util-replace");
+        let target = dir.join("memory.json");
+
+        atomic_write(&target, "first").expect("first write");
+        atomic_write(&target, "second, longer contents").expect("second write");
+
+        assert_eq!(
+            fs::read_to_string(&target).expect("read target"),
+            "second, longer contents"
+        );
+        assert_eq!(entries(&dir), vec!["memory.json".to_string()]);
+    }
+
+    #[test]
+    fn atomic_write_failure_keeps_target_and_cleans_temp() {
+        let dir = TempDir::new("util-failure");
+        // Renaming a file over a non-empty directory fails on every platform.
+        let target = dir.join("occupied");
+        fs::create_dir_all(target.join("child")).expect("create blocking dir");
+
+        assert!(atomic_write(&target, "data").is_err());
+        assert!(target.join("child").is_dir());
+        assert_eq!(entries(&dir), vec!["occupied".to_string()]);
+    }
+}
@@ -1,6 +1,10 @@
 //! Workspace helpers: detect project root and derive a slug for
 //! per-project memory scoping.
 
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 /// Detect the project root for a working directory.
@@ -11,6 +15,36 @@ pub fn detect_project_root(cwd: &Path) -> PathBuf {
     crate::git::resolve_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf())
 }
 
+/// Render a depth- and count-limited tree of `files` (slash-separated
+/// relative paths), with directories suffixed by `/`.
+///
+/// Returns an empty string when there are no files.
+pub fn project_layout(files: &[String], max_depth: usize, max_entries: usize) -> String {
+    // Component vectors sort parents directly before their children.
+    let mut entries: BTreeSet<(Vec<&str>, bool)> = BTreeSet::new();
+    for file in files {
+        let parts: Vec<&str> = file.split('/').filter(|p| !p.is_empty()).collect();
+        for depth in 1..=parts.len().min(max_depth) {
+            entries.insert((parts[..depth].to_vec(), depth < parts.len()));
+        }
+    }
+
+    let mut out = String::new();
+    for (parts, is_dir) in entries.iter().take(max_entries) {
+        let indent = "  ".repeat(parts.len() - 1);
+        let name = parts.last().copied().unwrap_or_default();
+        let suffix = if *is_dir { "/" } else { "" };
+        out.push_str(&format!("{indent}{name}{suffix}\n"));
+    }
+    if entries.len() > max_entries {
+        out.push_str(&format!(
+            "... ({} more entries not shown)\n",
+            entries.len() - max_entries
+        ));
+    }
+    out
+}
+
 /// Derive a URL/filesystem-safe slug from a path.
 ///
 /// Takes the last component (directory name) and lowercases it, replacing
@@ -21,6 +55,105 @@ pub fn slug_from_path(path: &Path) -> String {
         .map(|s| s.to_string_lossy().to_string())
         .unwrap_or_else(|| "project".into());
 
+    sanitize_slug(&name)
+}
+
+/// Validate a user-supplied `--project` slug, normalizing it the same way as
+/// [`slug_from_path`]. Prints a warning when the slug had to be changed.
+pub fn resolve_project_slug(slug: &str) -> anyhow::Result<String> {
+    let clean = sanitize_slug(slug);
+    if clean.is_empty() {
+        anyhow::bail!("invalid project slug '{slug}': use letters, digits, and hyphens");
+    }
+    if clean != slug {
+        eprintln!("warning: project slug '{slug}' normalized to '{clean}'");
+    }
+    Ok(clean)
+}
+
+/// Choose a bot's workspace slug.
+///
+/// Precedence: an explicit `--project`, then the slug derived from the
+/// working directory if that workspace already has history, then the bot's
+/// `default_project`, then the derived slug (starting a new workspace).
+pub fn choose_slug(
+    explicit: Option<&str>,
+    derived: String,
+    default_project: Option<&str>,
+    has_history: impl Fn(&str) -> bool,
+) -> anyhow::Result<String> {
+    if let Some(slug) = explicit {
+        return resolve_project_slug(slug);
+    }
+    match default_project {
+        Some(default) if !has_history(&derived) => {
+            let default = resolve_project_slug(default)?;
+            if default != derived {
+                eprintln!(
+                    "note: no history for workspace '{derived}'; using default_project '{default}'"
+                );
+            }
+            Ok(default)
+        }
+        _ => Ok(derived),
+    }
+}
+
+/// Where and when a bot last ran in one workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEntry {
+    /// Project root the slug was derived from (or the cwd for `--project`).
+    pub root: PathBuf,
+    pub last_run_at: DateTime<Utc>,
+}
+
+/// The workspaces a bot has run in, kept in `workspaces.json` by the runner.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkspaceRegistry {
+    /// Slug of the most recent run.
+    pub last_used: Option<String>,
+    pub workspaces: BTreeMap<String, WorkspaceEntry>,
+}
+
+impl WorkspaceRegistry {
+    /// Load the registry, or an empty one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        crate::util::atomic_write(path, format!("{json}\n"))
+    }
+
+    /// Note a run in `slug`, returning the previous run's workspace when it
+    /// was a different one.
+    pub fn record(&mut self, slug: &str, root: &Path) -> Option<(String, WorkspaceEntry)> {
+        let previous = self
+            .last_used
+            .as_ref()
+            .filter(|last| *last != slug)
+            .and_then(|last| Some((last.clone(), self.workspaces.get(last)?.clone())));
+        self.workspaces.insert(
+            slug.to_string(),
+            WorkspaceEntry {
+                root: root.to_path_buf(),
+                last_run_at: Utc::now(),
+            },
+        );
+        self.last_used = Some(slug.to_string());
+        previous
+    }
+}
+
+/// Lowercase `name`, map non-alphanumerics to hyphens, and collapse runs.
+fn sanitize_slug(name: &str) -> String {
     let slug: String = name
         .to_lowercase()
         .chars()
@@ -67,4 +200,68 @@ mod tests {
             "backend-api"
         );
     }
+
+    #[test]
+    fn project_layout_limits_depth_and_count() {
+        let files: Vec<String> = [
+            "Cargo.toml",
+            "src/main.rs",
+            "src/git/worktree/deep.rs",
+            "src-gen/out.rs",
+            "docs/guide.md",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        assert_eq!(
+            project_layout(&files, 2, 100),
+            "Cargo.toml\ndocs/\n  guide.md\nsrc/\n  git/\n  main.rs\nsrc-gen/\n  out.rs\n"
+        );
+        assert_eq!(
+            project_layout(&files, 1, 2),
+            "Cargo.toml\ndocs/\n... (2 more entries not shown)\n"
+        );
+        assert_eq!(project_layout(&[], 3, 10), "");
+    }
+
+    #[test]
+    fn project_slug_is_sanitized_like_paths() {
+        assert_eq!(resolve_project_slug("my-app").unwrap(), "my-app");
+        assert_eq!(resolve_project_slug("My App").unwrap(), "my-app");
+        assert_eq!(resolve_project_slug("../evil").unwrap(), "evil");
+        assert!(resolve_project_slug("..").is_err());
+        assert!(resolve_project_slug("").is_err());
+    }
+
+    #[test]
+    fn choose_slug_prefers_explicit_then_existing_then_default() {
+        let has_history = |slug: &str| slug == "api";
+        let choose = |explicit, derived: &str, default| {
+            choose_slug(explicit, derived.to_string(), default, has_history).unwrap()
+        };
+        assert_eq!(choose(Some("web"), "api", Some("home")), "web");
+        assert_eq!(choose(None, "api", Some("home")), "api");
+        assert_eq!(choose(None, "scratch", Some("home")), "home");
+        assert_eq!(choose(None, "scratch", None), "scratch");
+    }
+
+    #[test]
+    fn registry_reports_a_switch_to_another_workspace() {
+        let mut registry = WorkspaceRegistry::default();
+        assert!(registry.record("api", Path::new("/src/api")).is_none());
+        assert!(registry.record("api", Path::new("/src/api")).is_none());
+
+        let (slug, entry) = registry
+            .record("scratch", Path::new("/tmp/scratch"))
+            .expect("switch reported");
+        assert_eq!(slug, "api");
+        assert_eq!(entry.root, Path::new("/src/api"));
+        assert_eq!(registry.last_used.as_deref(), Some("scratch"));
+
+        let json = serde_json::to_string(&registry).unwrap();
+        let loaded: WorkspaceRegistry = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.workspaces.len(), 2);
+        assert_eq!(loaded.last_used.as_deref(), Some("scratch"));
+    }
 }